@@ -0,0 +1,269 @@
+use {
+    crate::{
+        controller::assert_callback_gas_limit,
+        error::Result,
+        state::{CONFIG, DENOM_CREATION_POOL},
+        timeout::assert_timeout_secs,
+        utils::Coins,
+        CustomMsg,
+    },
+    cosmwasm_std::{BankMsg, Coin, DepsMut, MessageInfo, Response, StdResult},
+};
+
+pub fn update_config(
+    deps:                       DepsMut,
+    info:                       MessageInfo,
+    default_account_code_id:    Option<u64>,
+    default_timeout_secs:       Option<u64>,
+    max_timeout_secs:           Option<u64>,
+    default_callback_gas_limit: Option<u64>,
+    max_callback_gas_limit:     Option<u64>,
+) -> Result<Response<CustomMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let mut cfg = CONFIG.load(deps.storage)?;
+
+    if let Some(code_id) = default_account_code_id {
+        cfg.default_account_code_id = code_id;
+    }
+
+    if let Some(secs) = default_timeout_secs {
+        cfg.default_timeout_secs = secs;
+    }
+
+    if let Some(secs) = max_timeout_secs {
+        cfg.max_timeout_secs = secs;
+    }
+
+    if let Some(gas_limit) = default_callback_gas_limit {
+        cfg.default_callback_gas_limit = gas_limit;
+    }
+
+    if let Some(gas_limit) = max_callback_gas_limit {
+        cfg.max_callback_gas_limit = gas_limit;
+    }
+
+    assert_timeout_secs(&cfg, cfg.default_timeout_secs)?;
+    assert_callback_gas_limit(&cfg, cfg.default_callback_gas_limit)?;
+
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_config")
+        .add_attribute("default_account_code_id", cfg.default_account_code_id.to_string())
+        .add_attribute("default_timeout_secs", cfg.default_timeout_secs.to_string())
+        .add_attribute("max_timeout_secs", cfg.max_timeout_secs.to_string())
+        .add_attribute("default_callback_gas_limit", cfg.default_callback_gas_limit.to_string())
+        .add_attribute("max_callback_gas_limit", cfg.max_callback_gas_limit.to_string()))
+}
+
+pub fn fund_denom_creation_pool(deps: DepsMut, info: MessageInfo) -> Result<Response<CustomMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    for coin in &info.funds {
+        DENOM_CREATION_POOL.update(deps.storage, &coin.denom, |balance| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_add(coin.amount)?)
+        })?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "fund_denom_creation_pool")
+        .add_attribute("amount", Coins::from(info.funds).to_string()))
+}
+
+pub fn withdraw_denom_creation_pool(
+    deps:      DepsMut,
+    info:      MessageInfo,
+    amount:    Vec<Coin>,
+    recipient: Option<String>,
+) -> Result<Response<CustomMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let recipient = match recipient {
+        Some(r) => deps.api.addr_validate(&r)?,
+        None => info.sender,
+    };
+
+    for coin in &amount {
+        DENOM_CREATION_POOL.update(deps.storage, &coin.denom, |balance| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(coin.amount)?)
+        })?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "withdraw_denom_creation_pool")
+        .add_attribute("recipient", &recipient)
+        .add_message(BankMsg::Send {
+            to_address: recipient.into(),
+            amount,
+        }))
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coin, coins,
+        testing::{mock_dependencies, mock_info},
+        OverflowError, OverflowOperation, StdError, Uint128,
+    };
+    use cw_ownable::OwnershipError;
+
+    use super::*;
+    use crate::{
+        error::Error,
+        msg::{Config, TokenFactoryFlavor, VoucherBackend},
+    };
+
+    #[test]
+    fn updating_config() {
+        let mut deps = mock_dependencies();
+
+        cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("larry")).unwrap();
+        CONFIG
+            .save(
+                deps.as_mut().storage,
+                &Config {
+                    default_account_code_id:    1,
+                    default_timeout_secs:       300,
+                    max_timeout_secs:           86_400,
+                    voucher_backend:            VoucherBackend::TokenFactory {
+                        flavor: TokenFactoryFlavor::Osmosis,
+                    },
+                    default_callback_gas_limit: 1_000_000,
+                    max_callback_gas_limit:     5_000_000,
+                },
+            )
+            .unwrap();
+
+        // non-owner cannot update config
+        {
+            let err = update_config(
+                deps.as_mut(),
+                mock_info("jake", &[]),
+                Some(2),
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap_err();
+            assert_eq!(err, Error::Ownership(OwnershipError::NotOwner));
+        }
+
+        // default callback gas limit cannot exceed the maximum
+        {
+            let err = update_config(
+                deps.as_mut(),
+                mock_info("larry", &[]),
+                None,
+                None,
+                None,
+                None,
+                Some(500_000),
+            )
+            .unwrap_err();
+            assert_eq!(err, Error::CallbackGasLimitTooHigh {
+                limit: 1_000_000,
+                max:   500_000,
+            });
+        }
+
+        // default timeout cannot exceed the maximum
+        {
+            let err = update_config(
+                deps.as_mut(),
+                mock_info("larry", &[]),
+                None,
+                Some(90_000),
+                None,
+                None,
+                None,
+            )
+            .unwrap_err();
+            assert_eq!(err, Error::TimeoutTooLong {
+                secs: 90_000,
+                max:  86_400,
+            });
+        }
+
+        // owner can update config; fields not provided are left unchanged
+        {
+            update_config(
+                deps.as_mut(),
+                mock_info("larry", &[]),
+                Some(2),
+                None,
+                None,
+                Some(2_000_000),
+                None,
+            )
+            .unwrap();
+
+            let cfg = CONFIG.load(deps.as_ref().storage).unwrap();
+            assert_eq!(
+                cfg,
+                Config {
+                    default_account_code_id:    2,
+                    default_timeout_secs:       300,
+                    max_timeout_secs:           86_400,
+                    voucher_backend:            VoucherBackend::TokenFactory {
+                        flavor: TokenFactoryFlavor::Osmosis,
+                    },
+                    default_callback_gas_limit: 2_000_000,
+                    max_callback_gas_limit:     5_000_000,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn funding_denom_creation_pool() {
+        let mut deps = mock_dependencies();
+
+        cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("larry")).unwrap();
+
+        // non-owner cannot fund the pool
+        {
+            let info = mock_info("jake", &coins(100, "uosmo"));
+            let err = fund_denom_creation_pool(deps.as_mut(), info).unwrap_err();
+            assert_eq!(err, Error::Ownership(OwnershipError::NotOwner));
+        }
+
+        fund_denom_creation_pool(deps.as_mut(), mock_info("larry", &coins(100, "uosmo"))).unwrap();
+        fund_denom_creation_pool(deps.as_mut(), mock_info("larry", &coins(50, "uosmo"))).unwrap();
+
+        let balance = DENOM_CREATION_POOL.load(deps.as_ref().storage, "uosmo").unwrap();
+        assert_eq!(balance, Uint128::new(150));
+
+        // cannot withdraw more than the pool holds
+        {
+            let err = withdraw_denom_creation_pool(
+                deps.as_mut(),
+                mock_info("larry", &[]),
+                vec![coin(151, "uosmo")],
+                None,
+            )
+            .unwrap_err();
+            let overflow = OverflowError::new(OverflowOperation::Sub, 150u128, 151u128);
+            assert_eq!(err, Error::Std(StdError::overflow(overflow)));
+        }
+
+        let res = withdraw_denom_creation_pool(
+            deps.as_mut(),
+            mock_info("larry", &[]),
+            vec![coin(120, "uosmo")],
+            Some("jake".into()),
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].msg, BankMsg::Send {
+            to_address: "jake".into(),
+            amount:     coins(120, "uosmo"),
+        }
+        .into());
+
+        let balance = DENOM_CREATION_POOL.load(deps.as_ref().storage, "uosmo").unwrap();
+        assert_eq!(balance, Uint128::new(30));
+    }
+}
@@ -0,0 +1,158 @@
+use {
+    crate::{
+        error::{Error, Result},
+        transfer::parse_cw20_denom,
+        utils::Json,
+    },
+    cosmwasm_std::{from_slice, Binary, Deps, Uint128},
+    cw20::{BalanceResponse, Cw20QueryMsg},
+    ics999::Comparison,
+    std::cmp::Ordering,
+};
+
+/// Assert that the balance of a token held by an account compares to the
+/// expected amount as specified
+pub fn assert_balance(
+    deps:     Deps,
+    address:  &str,
+    denom:    &str,
+    op:       &Comparison,
+    expected: Uint128,
+) -> Result<()> {
+    let balance = match parse_cw20_denom(denom) {
+        Some(contract_addr) => {
+            deps.querier
+                .query_wasm_smart::<BalanceResponse>(contract_addr, &Cw20QueryMsg::Balance {
+                    address: address.into(),
+                })?
+                .balance
+        },
+        None => deps.querier.query_balance(address, denom)?.amount,
+    };
+
+    if !compare(op, balance.cmp(&expected)) {
+        return Err(Error::AssertionFailed(format!(
+            "balance of `{denom}` held by `{address}` is `{balance}`, expecting {op:?} `{expected}`"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Assert that the value at the path in a JSON query response compares to the
+/// expected value as specified
+pub fn assert_query_response(
+    response: &Binary,
+    path:     &[String],
+    op:       &Comparison,
+    expected: &str,
+) -> Result<()> {
+    let json: Json = from_slice(response)?;
+
+    let actual = path
+        .iter()
+        .try_fold(&json, |json, key| json.get(key))
+        .ok_or_else(|| Error::AssertionFailed(format!("no value at path `{}`", path.join("."))))?;
+
+    let Json::Scalar(actual) = actual else {
+        return Err(Error::AssertionFailed(format!(
+            "value at path `{}` is not a string, number, boolean or null",
+            path.join("."),
+        )));
+    };
+
+    // compare as numbers if possible, otherwise only check for equality
+    let holds = match (actual.parse::<u128>(), expected.parse::<u128>()) {
+        (Ok(actual), Ok(expected)) => compare(op, actual.cmp(&expected)),
+        _ => match op {
+            Comparison::Eq => actual == expected,
+            Comparison::Ne => actual != expected,
+            _ => {
+                return Err(Error::AssertionFailed(format!(
+                    "can't compare non-numeric values `{actual}` and `{expected}` with {op:?}"
+                )));
+            },
+        },
+    };
+
+    if !holds {
+        return Err(Error::AssertionFailed(format!(
+            "value at path `{}` is `{actual}`, expecting {op:?} `{expected}`",
+            path.join("."),
+        )));
+    }
+
+    Ok(())
+}
+
+fn compare(op: &Comparison, ordering: Ordering) -> bool {
+    match op {
+        Comparison::Eq  => ordering.is_eq(),
+        Comparison::Ne  => ordering.is_ne(),
+        Comparison::Gt  => ordering.is_gt(),
+        Comparison::Gte => ordering.is_ge(),
+        Comparison::Lt  => ordering.is_lt(),
+        Comparison::Lte => ordering.is_le(),
+    }
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, testing::mock_dependencies};
+
+    use super::*;
+
+    #[test]
+    fn asserting_balance() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_balance("ica", vec![coin(100, "uosmo")]);
+
+        let assert = |op, amount: u128| {
+            assert_balance(deps.as_ref(), "ica", "uosmo", &op, Uint128::new(amount))
+        };
+
+        assert!(assert(Comparison::Gte, 100).is_ok());
+        assert!(assert(Comparison::Gt, 99).is_ok());
+        assert!(assert(Comparison::Lt, 101).is_ok());
+        assert!(assert(Comparison::Ne, 0).is_ok());
+
+        let err = assert(Comparison::Gt, 100).unwrap_err();
+        assert_eq!(
+            err,
+            Error::AssertionFailed(
+                "balance of `uosmo` held by `ica` is `100`, expecting Gt `100`".into(),
+            ),
+        );
+    }
+
+    #[test]
+    fn asserting_query_response() {
+        let response: Binary =
+            br#"{"assets":[{"denom":"uosmo","amount":"12345"},{"denom":"uatom","amount":"0"}],"shares":42,"frozen":false}"#
+                .into();
+
+        let assert = |path: &[&str], op, expected: &str| {
+            let path = path.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+            assert_query_response(&response, &path, &op, expected)
+        };
+
+        // numbers, whether in strings or not, are compared as such
+        assert!(assert(&["assets", "0", "amount"], Comparison::Gte, "12000").is_ok());
+        assert!(assert(&["assets", "1", "amount"], Comparison::Eq, "0").is_ok());
+        assert!(assert(&["shares"], Comparison::Lt, "100").is_ok());
+        assert!(assert(&["shares"], Comparison::Gt, "42").is_err());
+
+        // other values can only be compared for equality
+        assert!(assert(&["assets", "0", "denom"], Comparison::Eq, "uosmo").is_ok());
+        assert!(assert(&["frozen"], Comparison::Eq, "false").is_ok());
+        assert!(assert(&["frozen"], Comparison::Ne, "false").is_err());
+        assert!(assert(&["assets", "0", "denom"], Comparison::Gt, "uatom").is_err());
+
+        // the path must lead to a scalar value
+        assert!(assert(&["assets", "2", "amount"], Comparison::Eq, "0").is_err());
+        assert!(assert(&["assets", "denom"], Comparison::Eq, "uosmo").is_err());
+        assert!(assert(&["assets"], Comparison::Eq, "[]").is_err());
+    }
+}
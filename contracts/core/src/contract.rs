@@ -1,12 +1,15 @@
 use {
     crate::{
-        controller,
+        admin, controller,
         error::{Error, Result},
-        handshake, host,
-        msg::{AccountKey, Config, ExecuteMsg, QueryMsg},
-        query,
+        forward, handshake, host, migrations,
+        msg::{AccountKey, Config, ExecuteMsg, MigrateMsg, PacketKey, QueryMsg, RateLimitKey},
+        pause, query, rate_limit, recovery,
         state::CONFIG,
-        AFTER_ACTION, AFTER_ALL_ACTIONS, AFTER_CALLBACK, CONTRACT_NAME, CONTRACT_VERSION,
+        timeout,
+        CustomMsg,
+        AFTER_ACTION, AFTER_ALL_ACTIONS, AFTER_CALLBACK, AFTER_ISOLATED_ACTION, AFTER_SEND_PACKET,
+        CONTRACT_NAME, CONTRACT_VERSION,
     },
     cosmwasm_std::{
         entry_point, to_binary, Binary, Deps, DepsMut, Env, IbcBasicResponse, IbcChannelCloseMsg,
@@ -17,50 +20,166 @@ use {
 };
 
 #[entry_point]
-pub fn instantiate(deps: DepsMut, _: Env, _: MessageInfo, cfg: Config) -> Result<Response> {
+pub fn instantiate(
+    deps: DepsMut,
+    _:    Env,
+    info: MessageInfo,
+    cfg:  Config,
+) -> Result<Response<CustomMsg>> {
     cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    cw_ownable::initialize_owner(deps.storage, deps.api, Some(info.sender.as_str()))?;
+
+    controller::assert_callback_gas_limit(&cfg, cfg.default_callback_gas_limit)?;
+    timeout::assert_timeout_secs(&cfg, cfg.default_timeout_secs)?;
 
     CONFIG.save(deps.storage, &cfg)?;
 
-    Ok(Response::new())
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("owner", info.sender))
 }
 
 #[entry_point]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response> {
+pub fn execute(
+    deps: DepsMut,
+    env:  Env,
+    info: MessageInfo,
+    msg:  ExecuteMsg,
+) -> Result<Response<CustomMsg>> {
     match msg {
         ExecuteMsg::Dispatch {
             connection_id,
             actions,
             timeout,
-        } => {
-            if actions.is_empty() {
-                return Err(Error::EmptyActionQueue);
-            }
-
-            controller::dispatch(deps, env, info, connection_id, actions, timeout)
-        },
+            fee,
+            allow_failure,
+            callback_gas_limit,
+            callback,
+        } => controller::dispatch(
+            deps,
+            env,
+            info,
+            connection_id,
+            actions,
+            timeout,
+            fee,
+            allow_failure,
+            callback_gas_limit,
+            callback,
+        ),
+        ExecuteMsg::Receive(cw20_msg) => controller::receive_cw20(deps, env, info, cw20_msg),
+        ExecuteMsg::RetryCallback {
+            channel_id,
+            sequence,
+        } => controller::retry_callback(deps, channel_id, sequence),
+        ExecuteMsg::RetryForwardAck {
+            channel_id,
+            sequence,
+        } => forward::retry_forward_ack(deps, env, info, channel_id, sequence),
         ExecuteMsg::Handle {
             counterparty_endpoint,
             endpoint,
+            sequence,
             controller,
             actions,
             traces,
+            allow_failure,
         } => {
             if info.sender != env.contract.address {
                 return Err(Error::Unauthorized);
             }
 
-            host::handle(deps, env, counterparty_endpoint, endpoint, controller, actions, traces)
+            host::handle(
+                deps,
+                env,
+                counterparty_endpoint,
+                endpoint,
+                sequence,
+                controller,
+                actions,
+                traces,
+                allow_failure,
+            )
+        },
+        ExecuteMsg::HandleAction {} => {
+            if info.sender != env.contract.address {
+                return Err(Error::Unauthorized);
+            }
+
+            host::handle_action(deps, env)
+        },
+        ExecuteMsg::UpdateConfig {
+            default_account_code_id,
+            default_timeout_secs,
+            max_timeout_secs,
+            default_callback_gas_limit,
+            max_callback_gas_limit,
+        } => admin::update_config(
+            deps,
+            info,
+            default_account_code_id,
+            default_timeout_secs,
+            max_timeout_secs,
+            default_callback_gas_limit,
+            max_callback_gas_limit,
+        ),
+        ExecuteMsg::RetireChannel {
+            connection_id,
+        } => recovery::retire_channel(deps, info, connection_id),
+        ExecuteMsg::RemapAccounts {
+            connection_id,
+            start_after,
+            limit,
+        } => recovery::remap_accounts(deps, info, connection_id, start_after, limit),
+        ExecuteMsg::RemapVouchers {
+            connection_id,
+            start_after,
+            limit,
+        } => recovery::remap_vouchers(deps, env, info, connection_id, start_after, limit),
+        ExecuteMsg::RemapEscrows {
+            connection_id,
+            start_after,
+            limit,
+        } => recovery::remap_escrows(deps, info, connection_id, start_after, limit),
+        ExecuteMsg::Pause(scope) => pause::set_paused(deps, info, scope, true),
+        ExecuteMsg::Unpause(scope) => pause::set_paused(deps, info, scope, false),
+        ExecuteMsg::FundDenomCreationPool {} => admin::fund_denom_creation_pool(deps, info),
+        ExecuteMsg::WithdrawDenomCreationPool {
+            amount,
+            recipient,
+        } => admin::withdraw_denom_creation_pool(deps, info, amount, recipient),
+        ExecuteMsg::SetRateLimit {
+            connection_id,
+            denom,
+            rate_limit,
+        } => rate_limit::set_rate_limit(deps, info, connection_id, denom, rate_limit),
+        ExecuteMsg::SetDefaultTimeout {
+            connection_id,
+            timeout_secs,
+        } => timeout::set_default_timeout(deps, info, connection_id, timeout_secs),
+        ExecuteMsg::UpdateOwnership(action) => {
+            let ownership = cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
+
+            Ok(Response::new()
+                .add_attribute("method", "update_ownership")
+                .add_attributes(ownership.into_attributes()))
         },
     }
 }
 
 #[entry_point]
-pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response> {
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response<CustomMsg>> {
+    migrations::migrate(deps, env, msg)
+}
+
+#[entry_point]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response<CustomMsg>> {
     match msg.id {
-        AFTER_ACTION => host::after_action(deps, env, msg.result),
+        AFTER_ACTION => host::after_action(deps, msg.result),
         AFTER_ALL_ACTIONS => host::after_all_actions(msg.result),
-        AFTER_CALLBACK => controller::after_callback(msg.result.is_ok()),
+        AFTER_CALLBACK => controller::after_callback(deps, msg.result),
+        AFTER_ISOLATED_ACTION => host::after_isolated_action(deps, env, msg.result),
+        AFTER_SEND_PACKET => controller::after_send_packet(deps, msg.result),
         id => unreachable!("unknown reply ID: `{id}`"),
     }
 }
@@ -68,6 +187,7 @@ pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response> {
 #[entry_point]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
+        QueryMsg::Ownership {} => to_binary(&cw_ownable::get_ownership(deps.storage)?),
         QueryMsg::Config {} => to_binary(&query::config(deps)?),
         QueryMsg::DenomHash {
             trace,
@@ -94,6 +214,55 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             start_after,
             limit,
         } => to_binary(&query::active_channels(deps, start_after, limit)?),
+        QueryMsg::PauseStatus {} => to_binary(&pause::pause_status(deps.storage)?),
+        QueryMsg::RateLimit(RateLimitKey {
+            connection_id,
+            denom,
+        }) => to_binary(&query::rate_limit(deps, connection_id, denom)?),
+        QueryMsg::DenomCreationPool {} => to_binary(&query::denom_creation_pool(deps)?),
+        QueryMsg::PacketFee {
+            channel_id,
+            sequence,
+        } => to_binary(&query::packet_fee(deps, channel_id, sequence)?),
+        QueryMsg::RateLimits {
+            start_after,
+            limit,
+        } => to_binary(&query::rate_limits(deps, start_after, limit)?),
+        QueryMsg::Packet(PacketKey {
+            channel_id,
+            sequence,
+        }) => to_binary(&query::packet(deps, channel_id, sequence)?),
+        QueryMsg::PendingPackets {
+            controller,
+            start_after,
+            limit,
+        } => to_binary(&query::pending_packets(deps, controller, start_after, limit)?),
+        QueryMsg::FailedCallback(PacketKey {
+            channel_id,
+            sequence,
+        }) => to_binary(&query::failed_callback(deps, channel_id, sequence)?),
+        QueryMsg::FailedCallbacks {
+            start_after,
+            limit,
+        } => to_binary(&query::failed_callbacks(deps, start_after, limit)?),
+        QueryMsg::FailedForwardAck(PacketKey {
+            channel_id,
+            sequence,
+        }) => to_binary(&query::failed_forward_ack(deps, channel_id, sequence)?),
+        QueryMsg::FailedForwardAcks {
+            start_after,
+            limit,
+        } => to_binary(&query::failed_forward_acks(deps, start_after, limit)?),
+        QueryMsg::EscrowBalance {
+            channel_id,
+            denom,
+        } => to_binary(&query::escrow_balance(deps, channel_id, denom)?),
+        QueryMsg::VoucherSupply {
+            denom,
+        } => to_binary(&query::voucher_supply(deps, denom)?),
+        QueryMsg::DefaultTimeout {
+            connection_id,
+        } => to_binary(&query::default_timeout(deps, connection_id)?),
     }
 }
 
@@ -119,31 +288,40 @@ pub fn ibc_channel_connect(
     deps: DepsMut,
     _:    Env,
     msg:  IbcChannelConnectMsg,
-) -> Result<IbcBasicResponse> {
+) -> Result<IbcBasicResponse<CustomMsg>> {
     handshake::open_connect(deps, msg.channel(), msg.counterparty_version())
 }
 
 #[entry_point]
-pub fn ibc_channel_close(_: DepsMut, _: Env, msg: IbcChannelCloseMsg) -> Result<IbcBasicResponse> {
-    handshake::close(msg)
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _:    Env,
+    msg:  IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse<CustomMsg>> {
+    handshake::close(deps, msg)
 }
 
 #[entry_point]
 pub fn ibc_packet_receive(
-    _:   DepsMut,
-    env: Env,
-    msg: IbcPacketReceiveMsg,
-) -> Result<IbcReceiveResponse> {
-    host::packet_receive(env, msg.packet)
+    deps: DepsMut,
+    env:  Env,
+    msg:  IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse<CustomMsg>> {
+    host::packet_receive(deps, env, msg.packet)
 }
 
 #[entry_point]
-pub fn ibc_packet_ack(deps: DepsMut, env: Env, msg: IbcPacketAckMsg) -> Result<IbcBasicResponse> {
+pub fn ibc_packet_ack(
+    deps: DepsMut,
+    env:  Env,
+    msg:  IbcPacketAckMsg,
+) -> Result<IbcBasicResponse<CustomMsg>> {
     controller::packet_lifecycle_complete(
         deps,
         env,
         msg.original_packet,
         Some(msg.acknowledgement.data),
+        msg.relayer,
     )
 }
 
@@ -152,6 +330,6 @@ pub fn ibc_packet_timeout(
     deps: DepsMut,
     env:  Env,
     msg:  IbcPacketTimeoutMsg,
-) -> Result<IbcBasicResponse> {
-    controller::packet_lifecycle_complete(deps, env, msg.packet, None)
+) -> Result<IbcBasicResponse<CustomMsg>> {
+    controller::packet_lifecycle_complete(deps, env, msg.packet, None, msg.relayer)
 }
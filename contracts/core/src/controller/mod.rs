@@ -1,67 +1,116 @@
 use {
     crate::{
-        error::{Error, Result},
-        state::{ACTIVE_CHANNELS, CONFIG, DENOM_TRACES},
-        transfer::{burn, escrow, mint, release, TraceItem},
+        error::{redacted_error_code, Error, Result},
+        fee::{escrow_fee, settle_fee},
+        forward::{defer_completion, refunded_transfers, send_forward_ack, transfers, ForwardOrigin},
+        msg::{
+            CallbackOptions, Config, Cw20HookMsg, FailedCallback, FailedForwardAck, PacketFee,
+            PacketInfo, PacketStatus, Timeout,
+        },
+        pause::assert_not_paused,
+        rate_limit::{record_outflow, undo_outflow},
+        state::{
+            ACTIVE_CHANNELS, CALLBACK_GAS_LIMITS, CALLBACK_OPTIONS, CHANNEL_CONNECTIONS,
+            COMMITMENTS, CONFIG, DENOM_TRACES, FAILED_CALLBACKS, FAILED_FORWARD_ACKS, FORWARDS,
+            PACKETS, PENDING_CALLBACK, SENDING_PACKET,
+        },
+        timeout::resolve_timeout,
+        transfer::{
+            add_escrow, burn, cw20_denom, deduct_escrow, escrow, ics20_trace, mint, release,
+            TraceItem,
+        },
         utils::Coins,
-        AFTER_CALLBACK,
+        CustomMsg,
+        AFTER_CALLBACK, AFTER_SEND_PACKET,
     },
+    cosmwasm_schema::cw_serde,
     cosmwasm_std::{
-        from_slice, to_binary, Binary, Coin, DepsMut, Env, IbcBasicResponse,
-        IbcMsg, IbcPacket, IbcTimeout, MessageInfo, Response, Storage, SubMsg, WasmMsg,
+        attr, from_binary, from_slice, to_binary, Addr, Attribute, Binary, Coin, CosmosMsg, Deps,
+        DepsMut, Env, Event, IbcBasicResponse, IbcMsg, IbcPacket, MessageInfo, Response,
+        StdResult, Storage, SubMsg, SubMsgResult, WasmMsg,
+    },
+    cw20::Cw20ReceiveMsg,
+    cw_utils::nonpayable,
+    ics999::{
+        Action, CallbackMsg, ControllerExecuteMsg, ErrorCode, ForwardAck, PacketData,
+        PacketOutcome, Trace,
     },
-    ics999::{Action, CallbackMsg, ControllerExecuteMsg, PacketData, PacketOutcome, Trace},
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn dispatch(
-    deps:          DepsMut,
-    env:           Env,
-    info:          MessageInfo,
-    connection_id: String,
-    actions:       Vec<Action>,
-    timeout:       Option<IbcTimeout>,
-) -> Result<Response> {
+    mut deps:           DepsMut,
+    env:                Env,
+    info:               MessageInfo,
+    connection_id:      String,
+    actions:            Vec<Action>,
+    timeout:            Option<Timeout>,
+    fee:                Option<PacketFee>,
+    allow_failure:      bool,
+    callback_gas_limit: Option<u64>,
+    callback:           Option<CallbackOptions>,
+) -> Result<Response<CustomMsg>> {
     let received_funds = Coins::from(info.funds);
-    let mut sending_funds = Coins::empty();
-    let mut msgs = vec![];
-    let mut attrs = vec![];
-    let mut traces: Vec<Trace> = vec![];
 
-    // find the current chain's port and channel IDs
-    let endpoint = ACTIVE_CHANNELS.load(deps.storage, &connection_id)?;
+    if let Some(gas_limit) = callback_gas_limit {
+        let cfg = CONFIG.load(deps.storage)?;
+        assert_callback_gas_limit(&cfg, gas_limit)?;
+    }
 
-    // go through all transfer actions, either escrow or burn the coins based on
-    // whether the current chain is the source or the sink.
-    // also, compose the traces which will be included in the packet.
-    for action in &actions {
-        if let Action::Transfer { denom, amount, .. } = action {
-            let trace = trace_of(deps.storage, denom)?;
+    if let Some(address) = callback.as_ref().and_then(|cb| cb.address.as_deref()) {
+        deps.api.addr_validate(address)?;
+    }
 
-            let coin = Coin {
-                denom:  denom.clone(),
-                amount: *amount,
-            };
+    let SentPacket {
+        channel_id,
+        funds: mut sending_funds,
+        msgs,
+        send_msg,
+        mut attrs,
+        mut records,
+    } = send_packet(
+        deps.branch(),
+        &env,
+        &connection_id,
+        info.sender.into(),
+        actions,
+        timeout,
+        allow_failure,
+    )?;
 
-            if trace.sender_is_source(&endpoint) {
-                escrow(&coin, &mut attrs);
-            } else {
-                // note that we burn from the contract address instead of from
-                // info.sender
-                // this is because the token to be burned should have already
-                // been sent to the contract address along with the executeMsg
-                burn(&env.contract.address, coin.clone(), &mut msgs, &mut attrs);
-            }
+    // record the callback gas limit, if the user requested one other than the
+    // default
+    if let Some(gas_limit) = callback_gas_limit {
+        attrs.push(attr("callback_gas_limit", gas_limit.to_string()));
+    }
 
-            if !traces.iter().any(|trace| trace.denom == *denom) {
-                traces.push(trace.into_full_trace(denom));
-            }
+    // record the callback options, if the user provided any
+    if let Some(callback) = &callback {
+        if callback.disabled {
+            attrs.push(attr("callback", "disabled"));
+        } else if let Some(address) = &callback.address {
+            attrs.push(attr("callback_address", address));
+        }
+    }
+
+    // escrow the relayer fees, if any
+    if let Some(fee) = &fee {
+        let total = fee.total()?;
+        attrs.push(attr("fee_escrowed", total.to_string()));
 
+        for coin in Vec::<Coin>::from(total) {
             sending_funds.add(coin)?;
         }
     }
 
+    records.callback_gas_limit = callback_gas_limit;
+    records.callback = callback;
+    records.fee = fee;
+
+    SENDING_PACKET.save(deps.storage, &records)?;
+
     // the total amount of coins the user has sent to the contract must equal
-    // the amount they want to transfer via IBC
+    // the amount they want to transfer via IBC plus the relayer fees
     if received_funds != sending_funds {
         return Err(Error::FundsMismatch {
             actual:   received_funds,
@@ -69,36 +118,317 @@ pub fn dispatch(
         });
     }
 
+    // the packet's sequence is only known once it's sent, so its records are
+    // saved in the reply
+    Ok(Response::new()
+        .add_attribute("method", "dispatch")
+        .add_attribute("channel_id", channel_id)
+        .add_attributes(attrs)
+        .add_messages(msgs)
+        .add_submessage(SubMsg::reply_on_success(send_msg, AFTER_SEND_PACKET)))
+}
+
+/// What was done with a token transferred by a packet when the packet was sent
+#[cw_serde]
+pub enum Commitment {
+    /// The tokens were escrowed, to be released if refunded
+    Escrowed(Coin),
+
+    /// The vouchers were burned, to be minted if refunded
+    Burned(Coin),
+}
+
+/// What is recorded about a packet, under its sequence. A packet's sequence is
+/// assigned by the IBC module when it's sent, so the records are held onto
+/// until then.
+#[cw_serde]
+pub struct PacketRecords {
+    /// The packet, whose sequence is filled in once it's sent
+    pub info: PacketInfo,
+
+    /// What was done with each token transferred by the packet
+    pub commitments: Vec<Commitment>,
+
+    /// Relayer fees escrowed for the packet, if any
+    pub fee: Option<PacketFee>,
+
+    /// Gas limit of the callback, if the user requested one other than the
+    /// default
+    pub callback_gas_limit: Option<u64>,
+
+    /// How the controller is to be called back, if the user provided any
+    /// options
+    pub callback: Option<CallbackOptions>,
+
+    /// Where the packet came from, if it's forwarded on behalf of a counterparty
+    pub origin: Option<ForwardOrigin>,
+}
+
+/// A packet about to be sent: the messages for escrowing or burning the tokens
+/// it transfers, the message sending it, and its records, to be saved in
+/// `SENDING_PACKET` until its sequence is known in the reply to `send_msg`.
+pub(crate) struct SentPacket {
+    pub channel_id: String,
+    pub funds:      Coins,
+    pub msgs:       Vec<CosmosMsg<CustomMsg>>,
+    pub send_msg:   CosmosMsg<CustomMsg>,
+    pub attrs:      Vec<Attribute>,
+    pub records:    PacketRecords,
+}
+
+/// Compose a packet to be sent on the active channel of the connection.
+///
+/// Used both for packets dispatched by users, and for packets forwarded on
+/// behalf of a counterparty, in which case the tokens to be transferred are
+/// held by the contract itself.
+pub(crate) fn send_packet(
+    deps:          DepsMut,
+    env:           &Env,
+    connection_id: &str,
+    controller:    String,
+    actions:       Vec<Action>,
+    timeout:       Option<Timeout>,
+    allow_failure: bool,
+) -> Result<SentPacket> {
+    if actions.is_empty() {
+        return Err(Error::EmptyActionQueue);
+    }
+
+    let mut funds = Coins::empty();
+    let mut escrowed = Coins::empty();
+    let mut burned = Coins::empty();
+    let mut commitments = vec![];
+    let mut msgs = vec![];
+    let mut attrs = vec![];
+    let mut traces: Vec<Trace> = vec![];
+
+    assert_not_paused(deps.storage, connection_id, &actions)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // find the current chain's port and channel IDs
+    let endpoint = ACTIVE_CHANNELS.load(deps.storage, connection_id)?;
+
     // if the user does not specify a timeout, we use the default
-    let timeout = match timeout {
-        None => {
-            let cfg = CONFIG.load(deps.storage)?;
-            IbcTimeout::with_timestamp(env.block.time.plus_seconds(cfg.default_timeout_secs))
+    let timeout = resolve_timeout(deps.storage, &env.block, &cfg, connection_id, timeout)?;
+
+    // go through all transfer actions, including those to be forwarded further,
+    // either escrow or burn the coins based on whether the current chain is the
+    // source or the sink.
+    // also, compose the traces which will be included in the packet.
+    for coin in transfers(&actions) {
+        let trace = trace_of(deps.as_ref(), &coin.denom)?;
+
+        record_outflow(
+            deps.storage,
+            &deps.querier,
+            &env.block,
+            connection_id,
+            &coin.denom,
+            coin.amount,
+        )?;
+
+        if trace.sender_is_source(&endpoint) {
+            escrow(deps.storage, &endpoint.channel_id, &coin, &mut attrs)?;
+            escrowed.add(coin.clone())?;
+            commitments.push(Commitment::Escrowed(coin.clone()));
+        } else {
+            // note that we burn from the contract address instead of from
+            // info.sender
+            // this is because the token to be burned should have already
+            // been sent to the contract address along with the executeMsg
+            burn(
+                deps.storage,
+                &cfg.voucher_backend,
+                env.contract.address.as_str(),
+                coin.clone(),
+                &mut msgs,
+                &mut attrs,
+            )?;
+            burned.add(coin.clone())?;
+            commitments.push(Commitment::Burned(coin.clone()));
+        }
+
+        if !traces.iter().any(|trace| trace.denom == coin.denom) {
+            traces.push(trace.into_full_trace(&coin.denom));
+        }
+
+        funds.add(coin)?;
+    }
+
+    // record the packet, so that it can be looked up until it's complete, and
+    // what was done with each token, so that exactly that is undone if the
+    // transfer is refunded
+    let records = PacketRecords {
+        info:               PacketInfo {
+            channel_id:    endpoint.channel_id.clone(),
+            sequence:      0,
+            connection_id: connection_id.into(),
+            controller:    controller.clone(),
+            actions:       actions.iter().map(|action| action.ty().into()).collect(),
+            escrowed:      escrowed.into(),
+            burned:        burned.into(),
+            timeout:       timeout.clone(),
+            status:        PacketStatus::Pending {},
         },
-        Some(to) => to,
+        commitments,
+        fee:                None,
+        callback_gas_limit: None,
+        callback:           None,
+        origin:             None,
     };
 
+    let send_msg = IbcMsg::SendPacket {
+        channel_id: endpoint.channel_id.clone(),
+        data: to_binary(&PacketData {
+            controller,
+            actions,
+            traces,
+            forward_ack: None,
+            allow_failure,
+        })?,
+        timeout,
+    };
+
+    Ok(SentPacket {
+        channel_id: endpoint.channel_id,
+        funds,
+        msgs,
+        send_msg: send_msg.into(),
+        attrs,
+        records,
+    })
+}
+
+/// Save the records of the packet that was just sent under its sequence, which
+/// is read from the `send_packet` event emitted by the IBC module.
+///
+/// Returns the packet's channel ID and sequence.
+pub(crate) fn save_sent_packet(store: &mut dyn Storage, events: &[Event]) -> Result<(String, u64)> {
+    let PacketRecords {
+        mut info,
+        commitments,
+        fee,
+        callback_gas_limit,
+        callback,
+        origin,
+    } = SENDING_PACKET.load(store)?;
+
+    SENDING_PACKET.remove(store);
+
+    let sequence = packet_sequence(events).ok_or_else(|| Error::SequenceMissing {
+        channel_id: info.channel_id.clone(),
+    })?;
+
+    let channel_id = info.channel_id.clone();
+    let key = (channel_id.as_str(), sequence);
+
+    info.sequence = sequence;
+    PACKETS.save(store, key, &info)?;
+
+    if !commitments.is_empty() {
+        COMMITMENTS.save(store, key, &commitments)?;
+    }
+
+    if let Some(fee) = &fee {
+        escrow_fee(store, &channel_id, sequence, fee)?;
+    }
+
+    if let Some(gas_limit) = callback_gas_limit {
+        CALLBACK_GAS_LIMITS.save(store, key, &gas_limit)?;
+    }
+
+    if let Some(callback) = &callback {
+        CALLBACK_OPTIONS.save(store, key, callback)?;
+    }
+
+    if let Some(origin) = &origin {
+        FORWARDS.save(store, key, origin)?;
+    }
+
+    Ok((channel_id, sequence))
+}
+
+/// The sequence of a packet, from the `send_packet` event emitted by the IBC
+/// module upon sending it
+fn packet_sequence(events: &[Event]) -> Option<u64> {
+    events
+        .iter()
+        .filter(|event| event.ty == "send_packet")
+        .flat_map(|event| &event.attributes)
+        .find(|attr| attr.key == "packet_sequence")
+        .and_then(|attr| attr.value.parse().ok())
+}
+
+// reply on success, so the result can't be an error
+pub fn after_send_packet(deps: DepsMut, result: SubMsgResult) -> Result<Response<CustomMsg>> {
+    let events = result.unwrap().events;
+
+    let (channel_id, sequence) = save_sent_packet(deps.storage, &events)?;
+
     Ok(Response::new()
-        .add_attribute("method", "dispatch")
-        .add_attributes(attrs)
-        .add_messages(msgs)
-        .add_message(IbcMsg::SendPacket {
-            channel_id: endpoint.channel_id,
-            data: to_binary(&PacketData {
-                controller: info.sender.into(),
-                actions,
-                traces,
-            })?,
-            timeout,
-    }))
+        .add_attribute("method", "after_send_packet")
+        .add_attribute("channel_id", channel_id)
+        .add_attribute("sequence", sequence.to_string()))
+}
+
+/// Dispatch a packet with cw20 tokens sent via the cw20 `Send` method.
+///
+/// Within ICS-999, a cw20 token is represented by the denom `cw20:{token_addr}`.
+/// If it is a voucher created by this contract, it is burned or escrowed just
+/// like a tokenfactory voucher. Otherwise, this chain is the token's source, so
+/// it is escrowed, and a voucher with `cw20:{token_addr}` being the base denom
+/// is minted on the receiving chain.
+pub fn receive_cw20(
+    deps:     DepsMut,
+    env:      Env,
+    info:     MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response<CustomMsg>> {
+    // native funds can't be attached to a cw20 hook; they would go unaccounted
+    nonpayable(&info)?;
+
+    let denom = cw20_denom(info.sender.as_str());
+
+    let Cw20HookMsg::Dispatch {
+        connection_id,
+        actions,
+        timeout,
+        allow_failure,
+        callback_gas_limit,
+        callback,
+    } = from_binary(&cw20_msg.msg)?;
+
+    // treat the cw20 tokens as if they were sent as native funds by the user
+    let info = MessageInfo {
+        sender: deps.api.addr_validate(&cw20_msg.sender)?,
+        funds:  vec![Coin {
+            denom,
+            amount: cw20_msg.amount,
+        }],
+    };
+
+    dispatch(
+        deps,
+        env,
+        info,
+        connection_id,
+        actions,
+        timeout,
+        None,
+        allow_failure,
+        callback_gas_limit,
+        callback,
+    )
 }
 
 pub fn packet_lifecycle_complete(
-    deps:    DepsMut,
-    env:     Env,
-    packet:  IbcPacket,
-    ack_bin: Option<Binary>,
-) -> Result<IbcBasicResponse> {
+    mut deps: DepsMut,
+    env:      Env,
+    packet:   IbcPacket,
+    ack_bin:  Option<Binary>,
+    relayer:  Addr,
+) -> Result<IbcBasicResponse<CustomMsg>> {
     let mut msgs = vec![];
     let mut attrs = vec![];
 
@@ -109,82 +439,341 @@ pub fn packet_lifecycle_complete(
     let ack = ack_bin.map(|bin| from_slice(&bin)).transpose()?;
     let outcome: PacketOutcome = ack.into();
 
-    // process refund if the packet timed out or failed
-    if should_refund(&outcome) {
-        for action in &packet_data.actions {
-            if let Action::Transfer { denom, amount, .. } = action {
-                let trace = trace_of(deps.storage, denom)?;
-
-                let coin = Coin {
-                    denom:  denom.clone(),
-                    amount: *amount,
-                };
-
-                // do the reverse of what was done in `act`
-                // if the tokens were escrowed, then release them
-                // if the tokens were burned, then mint them
-                if trace.sender_is_source(&packet.src) {
-                    release(coin, &packet_data.controller, &mut msgs, &mut attrs);
-                } else {
-                    mint(&env.contract.address, &packet_data.controller, coin,  &mut msgs, &mut attrs);
-                }
-            }
-        }
-    }
-
-    Ok(IbcBasicResponse::new()
+    let res = IbcBasicResponse::new()
         .add_attribute("method", "packet_lifecycle_complete")
         .add_attribute("controller", &packet_data.controller)
         .add_attribute("port_id", &packet.src.port_id)
         .add_attribute("channel_id", &packet.src.channel_id)
         .add_attribute("sequence", packet.sequence.to_string())
-        .add_attribute("outcome", outcome.ty())
+        .add_attribute("outcome", outcome.ty());
+
+    // the packet relayed the outcome of a forwarded packet, so there is no
+    // controller to call back. the counterparty can't complete the original
+    // packet without it, so if it timed out, send it again. if the counterparty
+    // rejected it, hold onto it, to be sent again by the admin once whatever
+    // caused the rejection is fixed
+    if packet_data.forward_ack.is_some() {
+        match outcome {
+            PacketOutcome::Success(_) => {},
+            PacketOutcome::Timeout {} => {
+                msgs.push(send_forward_ack(
+                    deps.storage,
+                    &env,
+                    &packet.src.channel_id,
+                    packet_data,
+                )?);
+            },
+            outcome => {
+                let key = (packet.src.channel_id.as_str(), packet.sequence);
+                FAILED_FORWARD_ACKS.save(deps.storage, key, &FailedForwardAck {
+                    channel_id: packet.src.channel_id.clone(),
+                    sequence:   packet.sequence,
+                    packet_data,
+                    outcome,
+                })?;
+            },
+        }
+
+        return Ok(res.add_messages(msgs));
+    }
+
+    // pay the relayer and refund the unused fees
+    settle_fee(
+        deps.storage,
+        &packet,
+        &relayer,
+        &packet_data.controller,
+        matches!(outcome, PacketOutcome::Timeout {}),
+        &mut msgs,
+        &mut attrs,
+    )?;
+
+    // if any of the actions were forwarded to another chain, the packet isn't
+    // complete until the outcomes of the forwarded packets are relayed back
+    let Some(outcome) = defer_completion(deps.storage, &packet, outcome)? else {
+        return Ok(res
+            .add_attribute("complete", "false")
+            .add_attributes(attrs)
+            .add_messages(msgs));
+    };
+
+    let callback =
+        complete_packet(deps.branch(), &env, &packet, packet_data, outcome, &mut msgs, &mut attrs)?;
+
+    Ok(res
         .add_attributes(attrs)
         .add_messages(msgs)
-        .add_submessage(SubMsg::reply_always(
-            WasmMsg::Execute {
-                contract_addr: packet_data.controller,
-                msg: to_binary(&ControllerExecuteMsg::Ics999(CallbackMsg {
-                    endpoint: packet.src,
-                    sequence: packet.sequence,
-                    outcome,
-                }))?,
-                funds: vec![],
+        .add_submessages(callback))
+}
+
+/// Refund the tokens that didn't make it to the destination, then call back
+/// the controller, whose callback submessage is returned.
+///
+/// If this chain sent the packet on behalf of a counterparty, the tokens are
+/// instead returned to the chain they were forwarded from, and the outcome is
+/// relayed back there.
+pub(crate) fn complete_packet(
+    deps:        DepsMut,
+    env:         &Env,
+    packet:      &IbcPacket,
+    packet_data: PacketData,
+    outcome:     PacketOutcome,
+    msgs:        &mut Vec<CosmosMsg<CustomMsg>>,
+    attrs:       &mut Vec<Attribute>,
+) -> Result<Option<SubMsg<CustomMsg>>> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let connection_id = CHANNEL_CONNECTIONS.load(deps.storage, &packet.src.channel_id)?;
+
+    let key = (packet.src.channel_id.as_str(), packet.sequence);
+    let origin = FORWARDS.may_load(deps.storage, key)?;
+
+    // packets sent before packets were recorded have no record to update
+    if let Some(mut info) = PACKETS.may_load(deps.storage, key)? {
+        info.status = PacketStatus::Complete {
+            outcome: outcome.ty().into(),
+        };
+        PACKETS.save(deps.storage, key, &info)?;
+    }
+
+    // forwarded tokens are refunded to the contract itself
+    let owner = match &origin {
+        Some(_) => env.contract.address.as_str(),
+        None => packet_data.controller.as_str(),
+    };
+
+    let mut commitments = COMMITMENTS.may_load(deps.storage, key)?;
+    COMMITMENTS.remove(deps.storage, key);
+
+    for coin in refunded_transfers(&packet_data.actions, &outcome) {
+        undo_outflow(deps.storage, &env.block, &connection_id, &coin.denom, coin.amount)?;
+
+        // do the reverse of what was done in `send_packet`, as recorded then,
+        // so that it isn't affected by changes to the traces since
+        // if the tokens were escrowed, then release them
+        // if the tokens were burned, then mint them
+        let escrowed = match &mut commitments {
+            Some(commitments) => {
+                take_commitment(commitments, &coin).ok_or_else(|| Error::CommitmentNotFound {
+                    channel_id: packet.src.channel_id.clone(),
+                    sequence:   packet.sequence,
+                    coin:       coin.clone(),
+                })?
+            },
+            // the packet was sent before commitments were recorded, and so
+            // before ICS-20 traces were resolved
+            None => stored_trace_of(deps.storage, &coin.denom)?.sender_is_source(&packet.src),
+        };
+
+        // forwarded tokens are already held by the contract, so they only need
+        // to be deducted from the channel's escrow
+        if escrowed {
+            if origin.is_none() {
+                release(deps.storage, &packet.src.channel_id, coin.clone(), owner, msgs, attrs)?;
+            } else {
+                deduct_escrow(deps.storage, &packet.src.channel_id, &coin)?;
+            }
+        } else {
+            mint(
+                deps.storage,
+                &cfg.voucher_backend,
+                env.contract.address.as_str(),
+                owner,
+                coin.clone(),
+                msgs,
+                attrs,
+            )?;
+        }
+
+        // return the tokens to the chain they were forwarded from. vouchers
+        // minted upon receiving them are burned; tokens released upon
+        // receiving them are simply held in escrow again
+        if let Some(origin) = &origin {
+            let trace = stored_trace_of(deps.storage, &coin.denom)?;
+            if trace.path.last() == Some(&origin.endpoint) {
+                burn(deps.storage, &cfg.voucher_backend, owner, coin, msgs, attrs)?;
+            } else {
+                add_escrow(deps.storage, &origin.endpoint.channel_id, &coin)?;
+            }
+        }
+    }
+
+    let Some(origin) = origin else {
+        let callback = CALLBACK_OPTIONS.may_load(deps.storage, key)?.unwrap_or_default();
+        let gas_limit = CALLBACK_GAS_LIMITS
+            .may_load(deps.storage, key)?
+            .unwrap_or(cfg.default_callback_gas_limit);
+
+        CALLBACK_OPTIONS.remove(deps.storage, key);
+        CALLBACK_GAS_LIMITS.remove(deps.storage, key);
+
+        if callback.disabled {
+            return Ok(None);
+        }
+
+        let pending = PendingCallback {
+            address: callback.address.unwrap_or(packet_data.controller),
+            msg:     CallbackMsg {
+                endpoint:      packet.src.clone(),
+                sequence:      packet.sequence,
+                outcome,
+                callback_data: callback.callback_data,
             },
-            AFTER_CALLBACK,
-        )))
+        };
+
+        // in case the callback fails, it is saved to be retried
+        PENDING_CALLBACK.save(deps.storage, &pending)?;
+
+        // the callback is gas-limited, so that a controller can't make the
+        // packet impossible to complete by consuming all the relayer's gas
+        return Ok(Some(
+            SubMsg::reply_always(pending.into_wasm_msg()?, AFTER_CALLBACK)
+                .with_gas_limit(gas_limit),
+        ));
+    };
+
+    FORWARDS.remove(deps.storage, key);
+
+    attrs.push(attr("forwarded_from", &origin.endpoint.channel_id));
+    attrs.push(attr("forwarded_from_sequence", origin.sequence.to_string()));
+
+    msgs.push(send_forward_ack(deps.storage, env, &origin.endpoint.channel_id, PacketData {
+        controller:    origin.controller,
+        actions:       vec![],
+        traces:        vec![],
+        forward_ack:   Some(ForwardAck {
+            sequence:             origin.sequence,
+            forwarded_channel_id: packet.src.channel_id.clone(),
+            forwarded_sequence:   packet.sequence,
+            outcome,
+        }),
+        allow_failure: false,
+    })?);
+
+    Ok(None)
+}
+
+/// A callback to a controller, whose result is yet to be known
+#[cw_serde]
+pub struct PendingCallback {
+    pub address: String,
+    pub msg:     CallbackMsg,
+}
+
+impl PendingCallback {
+    fn into_wasm_msg(self) -> StdResult<WasmMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: self.address,
+            msg:           to_binary(&ControllerExecuteMsg::Ics999(self.msg))?,
+            funds:         vec![],
+        })
+    }
 }
 
 // this method must succeed whether the callback was successful or not
-// if the callback failed, we simply log it here
-pub fn after_callback(success: bool) -> Result<Response> {
-    Ok(Response::new()
+// if the callback failed, we log it here, noting whether it ran out of gas, as
+// opposed to the controller rejecting it, and save it to be retried
+pub fn after_callback(deps: DepsMut, result: SubMsgResult) -> Result<Response<CustomMsg>> {
+    let callback = PENDING_CALLBACK.load(deps.storage)?;
+    PENDING_CALLBACK.remove(deps.storage);
+
+    let mut res = Response::new()
         .add_attribute("method", "after_callback")
-        .add_attribute("success", success.to_string()))
+        .add_attribute("success", result.is_ok().to_string());
+
+    if let SubMsgResult::Err(error) = result {
+        let out_of_gas = redacted_error_code(&error) == ErrorCode::OutOfGas;
+        res = res.add_attribute("out_of_gas", out_of_gas.to_string());
+
+        let failed = FailedCallback {
+            address: callback.address,
+            msg:     callback.msg,
+            error,
+        };
+        let key = (failed.msg.endpoint.channel_id.as_str(), failed.msg.sequence);
+        FAILED_CALLBACKS.save(deps.storage, key, &failed)?;
+    }
+
+    Ok(res)
 }
 
-/// Find the trace associated with a denom.
+/// Deliver a failed callback to the controller again.
 ///
-/// If there isn't a trace stored for this denom, then the current chain must be
-/// the source. In this case, initialize a new trace with the current chain
-/// being the first and only step in the path.
-fn trace_of(store: &dyn Storage, denom: &str) -> Result<TraceItem> {
-    Ok(DENOM_TRACES
-        .may_load(store, denom)?
-        .unwrap_or_else(|| TraceItem::new(denom)))
+/// Anyone may do this, since the callback is the same as the original one. It
+/// isn't gas-limited, as it's the caller who pays for the gas. If it fails
+/// again, the whole transaction is reverted, so the callback remains saved.
+pub fn retry_callback(
+    deps:       DepsMut,
+    channel_id: String,
+    sequence:   u64,
+) -> Result<Response<CustomMsg>> {
+    let key = (channel_id.as_str(), sequence);
+    let callback = FAILED_CALLBACKS.load(deps.storage, key)?;
+    FAILED_CALLBACKS.remove(deps.storage, key);
+
+    let msg = PendingCallback {
+        address: callback.address,
+        msg:     callback.msg,
+    }
+    .into_wasm_msg()?;
+
+    Ok(Response::new()
+        .add_attribute("method", "retry_callback")
+        .add_attribute("channel_id", channel_id)
+        .add_attribute("sequence", sequence.to_string())
+        .add_message(msg))
+}
+
+/// The callback gas limit requested by a user, or the default one set by the
+/// admin, must not exceed the maximum.
+pub(crate) fn assert_callback_gas_limit(cfg: &Config, gas_limit: u64) -> Result<()> {
+    if gas_limit > cfg.max_callback_gas_limit {
+        return Err(Error::CallbackGasLimitTooHigh {
+            limit: gas_limit,
+            max:   cfg.max_callback_gas_limit,
+        });
+    }
+
+    Ok(())
 }
 
-fn should_refund(outcome: &PacketOutcome) -> bool {
-    match outcome {
-        // packet timed out -- refund
-        PacketOutcome::Timeout {} => true,
+/// Remove the commitment of a refunded transfer from the packet's record.
+/// Returns whether the tokens were escrowed, as opposed to burned, or `None` if
+/// there is no such commitment.
+fn take_commitment(commitments: &mut Vec<Commitment>, coin: &Coin) -> Option<bool> {
+    let index = commitments.iter().position(|commitment| match commitment {
+        Commitment::Escrowed(committed) | Commitment::Burned(committed) => committed == coin,
+    })?;
 
-        // packet acknowledged but failed -- refund
-        PacketOutcome::Failed(_) => true,
+    Some(matches!(commitments.remove(index), Commitment::Escrowed(_)))
+}
+
+/// Find the trace associated with a denom:
+/// - vouchers created by ICS-999 have their traces saved in the contract store
+/// - `ibc/` tokens created by ICS-20 have their traces resolved by querying the
+///   transfer module, so that the asset keeps its provenance. If the chain
+///   doesn't support the query, or it fails, they are treated as native tokens,
+///   as they were before ICS-20 traces were resolved
+/// - otherwise, the token is native to this chain
+fn trace_of(deps: Deps, denom: &str) -> StdResult<TraceItem> {
+    if let Some(trace) = DENOM_TRACES.may_load(deps.storage, denom)? {
+        return Ok(trace);
+    }
 
-        // packet acknowledged and succeeded -- no refund
-        PacketOutcome::Success(_) => false,
+    if let Ok(Some(trace)) = ics20_trace(&deps.querier, denom) {
+        return Ok(trace);
     }
+
+    Ok(TraceItem::new(denom))
+}
+
+/// Find the trace associated with a denom without resolving ICS-20 traces.
+///
+/// Used when completing packets, which must not depend on a query that may
+/// fail. ICS-20 traces don't matter there: packets sent before commitments were
+/// recorded treated `ibc/` tokens as native, and an ICS-20 path never ends at
+/// an ICS-999 endpoint.
+fn stored_trace_of(store: &dyn Storage, denom: &str) -> StdResult<TraceItem> {
+    Ok(DENOM_TRACES.may_load(store, denom)?.unwrap_or_else(|| TraceItem::new(denom)))
 }
 
 // ----------------------------------- Tests -----------------------------------
@@ -192,13 +781,32 @@ fn should_refund(outcome: &PacketOutcome) -> bool {
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::{
+        attr, coin,
         testing::{mock_dependencies, mock_env, mock_info},
-        IbcEndpoint, Uint128,
+        CosmosMsg, IbcEndpoint, IbcTimeout, QuerierWrapper, ReplyOn, SubMsgResponse, Uint128,
     };
+    use cw_utils::PaymentError;
+    use ics999::RegisterOptions;
 
-    use crate::msg::Config;
+    use crate::{
+        msg::{Config, TokenFactoryFlavor, VoucherBackend},
+        query,
+        state::VOUCHER_SUPPLIES,
+        token_backend::tests::mock_querier,
+    };
     use super::*;
 
+    /// The reply to the submessage sending a packet, with the `send_packet`
+    /// event emitted by the IBC module
+    fn send_packet_reply(sequence: u64) -> SubMsgResult {
+        SubMsgResult::Ok(SubMsgResponse {
+            events: vec![
+                Event::new("send_packet").add_attribute("packet_sequence", sequence.to_string())
+            ],
+            data:   None,
+        })
+    }
+
     #[test]
     fn asserting_funds() {
         struct TestCase {
@@ -298,7 +906,16 @@ mod tests {
 
             let mock_connection_id = "connection-0";
             let mock_active_channel = IbcEndpoint { port_id: "port-0".into(), channel_id: "channel-0".into() };
-            let mock_cfg = Config { default_account_code_id: 1, default_timeout_secs: 300 };
+            let mock_cfg = Config {
+                default_account_code_id:    1,
+                default_timeout_secs:       300,
+                max_timeout_secs:           86_400,
+                voucher_backend:            VoucherBackend::TokenFactory {
+                    flavor: TokenFactoryFlavor::Osmosis,
+                },
+                default_callback_gas_limit: 1_000_000,
+                max_callback_gas_limit:     5_000_000,
+            };
 
             CONFIG
                 .save(deps.as_mut().storage, &mock_cfg)
@@ -314,6 +931,10 @@ mod tests {
                 mock_connection_id.into(),
                 actions.clone(),
                 None,
+                None,
+                false,
+                None,
+                None,
             );
 
             if testcase.should_ok {
@@ -324,6 +945,533 @@ mod tests {
         }
     }
 
+    #[test]
+    fn dispatching_cw20() {
+        let mut deps = mock_dependencies();
+
+        let endpoint = IbcEndpoint { port_id: "port-0".into(), channel_id: "channel-0".into() };
+        let mock_cfg = Config {
+            default_account_code_id:    1,
+            default_timeout_secs:       300,
+            max_timeout_secs:           86_400,
+            voucher_backend:            VoucherBackend::Cw20 { code_id: 2 },
+            default_callback_gas_limit: 1_000_000,
+            max_callback_gas_limit:     5_000_000,
+        };
+
+        CONFIG.save(deps.as_mut().storage, &mock_cfg).unwrap();
+        ACTIVE_CHANNELS.save(deps.as_mut().storage, "connection-0", &endpoint).unwrap();
+
+        // a voucher that was received through the channel
+        let trace = TraceItem {
+            base_denom: "uatom".into(),
+            path:       vec![endpoint],
+        };
+        DENOM_TRACES.save(deps.as_mut().storage, "cw20:voucher", &trace).unwrap();
+        VOUCHER_SUPPLIES
+            .save(deps.as_mut().storage, "cw20:voucher", &Uint128::new(100_000))
+            .unwrap();
+
+        let cw20_msg = |amount: u128| Cw20ReceiveMsg {
+            sender: "larry".into(),
+            amount: Uint128::new(amount),
+            msg:    to_binary(&Cw20HookMsg::Dispatch {
+                connection_id:      "connection-0".into(),
+                actions:            vec![Action::Transfer {
+                    denom:     "cw20:voucher".into(),
+                    amount:    Uint128::new(12345),
+                    recipient: None,
+                }],
+                timeout:            None,
+                allow_failure:      false,
+                callback_gas_limit: None,
+                callback:           None,
+            })
+            .unwrap(),
+        };
+
+        // the amount of vouchers sent must match the transfer
+        {
+            let info = mock_info("voucher", &[]);
+            let err = receive_cw20(deps.as_mut(), mock_env(), info, cw20_msg(100)).unwrap_err();
+            assert!(matches!(err, Error::FundsMismatch { .. }));
+        }
+
+        // native funds can't be sent along with the cw20 tokens
+        {
+            let info = mock_info("voucher", &[coin(100, "uosmo")]);
+            let err = receive_cw20(deps.as_mut(), mock_env(), info, cw20_msg(12345)).unwrap_err();
+            assert!(matches!(err, Error::Payment(PaymentError::NonPayable {})));
+        }
+
+        // the voucher is sent back to where it came from, so it is burned
+        let info = mock_info("voucher", &[]);
+        let res = receive_cw20(deps.as_mut(), mock_env(), info, cw20_msg(12345)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            WasmMsg::Execute {
+                contract_addr: "voucher".into(),
+                msg:           to_binary(&cw20::Cw20ExecuteMsg::Burn {
+                    amount: Uint128::new(12345),
+                })
+                .unwrap(),
+                funds:         vec![],
+            }
+            .into(),
+        );
+
+        // a cw20 token that originates from this chain is escrowed
+        let mut msg = cw20_msg(12345);
+        msg.msg = to_binary(&Cw20HookMsg::Dispatch {
+            connection_id:      "connection-0".into(),
+            actions:            vec![Action::Transfer {
+                denom:     "cw20:token".into(),
+                amount:    Uint128::new(12345),
+                recipient: None,
+            }],
+            timeout:            None,
+            allow_failure:      false,
+            callback_gas_limit: None,
+            callback:           None,
+        })
+        .unwrap();
+
+        let res = receive_cw20(deps.as_mut(), mock_env(), mock_info("token", &[]), msg).unwrap();
+        assert!(res.attributes.contains(&attr("action", "escrow")));
+
+        let CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) = &res.messages[0].msg else {
+            panic!("expecting a SendPacket msg");
+        };
+        let packet_data: PacketData = from_binary(data).unwrap();
+        assert_eq!(packet_data.traces, vec![Trace {
+            denom:      "cw20:token".into(),
+            base_denom: "cw20:token".into(),
+            path:       vec![],
+        }]);
+    }
+
+    #[test]
+    fn calling_back() {
+        let mut deps = mock_dependencies();
+
+        let endpoint = IbcEndpoint { port_id: "port-0".into(), channel_id: "channel-0".into() };
+        let mock_cfg = Config {
+            default_account_code_id:    1,
+            default_timeout_secs:       300,
+            max_timeout_secs:           86_400,
+            voucher_backend:            VoucherBackend::TokenFactory {
+                flavor: TokenFactoryFlavor::Osmosis,
+            },
+            default_callback_gas_limit: 1_000_000,
+            max_callback_gas_limit:     5_000_000,
+        };
+
+        CONFIG.save(deps.as_mut().storage, &mock_cfg).unwrap();
+        ACTIVE_CHANNELS.save(deps.as_mut().storage, "connection-0", &endpoint).unwrap();
+        CHANNEL_CONNECTIONS
+            .save(deps.as_mut().storage, "channel-0", &"connection-0".into())
+            .unwrap();
+
+        let actions = vec![Action::RegisterAccount(RegisterOptions::Default {
+            salt: None,
+        })];
+
+        let dispatch_with = |deps: DepsMut, gas_limit, callback| {
+            dispatch(
+                deps,
+                mock_env(),
+                mock_info("larry", &[]),
+                "connection-0".into(),
+                actions.clone(),
+                None,
+                None,
+                false,
+                gas_limit,
+                callback,
+            )
+        };
+
+        let complete = |deps: DepsMut, sequence: u64| {
+            let packet = IbcPacket::new(
+                to_binary(&PacketData {
+                    controller:    "larry".into(),
+                    actions:       actions.clone(),
+                    traces:        vec![],
+                    forward_ack:   None,
+                    allow_failure: false,
+                })
+                .unwrap(),
+                endpoint.clone(),
+                IbcEndpoint { port_id: "port-1".into(), channel_id: "channel-1".into() },
+                sequence,
+                IbcTimeout::with_timestamp(mock_env().block.time),
+            );
+            let packet_data = from_binary(&packet.data).unwrap();
+            complete_packet(
+                deps,
+                &mock_env(),
+                &packet,
+                packet_data,
+                PacketOutcome::Timeout {},
+                &mut vec![],
+                &mut vec![],
+            )
+            .unwrap()
+        };
+
+        // the requested gas limit can't exceed the maximum
+        {
+            let err = dispatch_with(deps.as_mut(), Some(5_000_001), None).unwrap_err();
+            assert_eq!(err, Error::CallbackGasLimitTooHigh {
+                limit: 5_000_001,
+                max:   5_000_000,
+            });
+        }
+
+        // no gas limit requested: the default one is used
+        {
+            dispatch_with(deps.as_mut(), None, None).unwrap();
+            after_send_packet(deps.as_mut(), send_packet_reply(1)).unwrap();
+
+            let callback = complete(deps.as_mut(), 1).unwrap();
+            assert_eq!(callback.gas_limit, Some(1_000_000));
+        }
+
+        // the requested gas limit is used, and deleted once the packet completes
+        {
+            let res = dispatch_with(deps.as_mut(), Some(2_000_000), None).unwrap();
+            assert!(res.attributes.contains(&attr("callback_gas_limit", "2000000")));
+            after_send_packet(deps.as_mut(), send_packet_reply(2)).unwrap();
+
+            let callback = complete(deps.as_mut(), 2).unwrap();
+            assert_eq!(callback.gas_limit, Some(2_000_000));
+            assert!(!CALLBACK_GAS_LIMITS.has(deps.as_ref().storage, ("channel-0", 2)));
+        }
+
+        // the callback address must be valid
+        {
+            let callback = CallbackOptions {
+                address: Some("x".into()),
+                ..Default::default()
+            };
+            let err = dispatch_with(deps.as_mut(), None, Some(callback)).unwrap_err();
+            assert!(matches!(err, Error::Std(_)));
+        }
+
+        // the callback is disabled
+        {
+            let callback = CallbackOptions {
+                disabled: true,
+                ..Default::default()
+            };
+            let res = dispatch_with(deps.as_mut(), None, Some(callback)).unwrap();
+            assert!(res.attributes.contains(&attr("callback", "disabled")));
+            after_send_packet(deps.as_mut(), send_packet_reply(3)).unwrap();
+
+            assert!(complete(deps.as_mut(), 3).is_none());
+            assert!(!CALLBACK_OPTIONS.has(deps.as_ref().storage, ("channel-0", 3)));
+        }
+
+        // the callback is sent to another contract, along with the user's data
+        {
+            let callback = CallbackOptions {
+                disabled:      false,
+                address:       Some("jake".into()),
+                callback_data: Some(b"hello".into()),
+            };
+            let res = dispatch_with(deps.as_mut(), None, Some(callback)).unwrap();
+            assert!(res.attributes.contains(&attr("callback_address", "jake")));
+            after_send_packet(deps.as_mut(), send_packet_reply(4)).unwrap();
+
+            let callback = complete(deps.as_mut(), 4).unwrap();
+            assert_eq!(callback.msg, CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "jake".into(),
+                msg:           to_binary(&ControllerExecuteMsg::Ics999(CallbackMsg {
+                    endpoint:      endpoint.clone(),
+                    sequence:      4,
+                    outcome:       PacketOutcome::Timeout {},
+                    callback_data: Some(b"hello".into()),
+                }))
+                .unwrap(),
+                funds:         vec![],
+            }));
+            assert!(!CALLBACK_OPTIONS.has(deps.as_ref().storage, ("channel-0", 4)));
+        }
+
+        // a callback that ran out of gas is logged as such, and saved to be
+        // retried
+        {
+            let result = SubMsgResult::Err("codespace: sdk, code: 11".into());
+            let res = after_callback(deps.as_mut(), result).unwrap();
+            assert!(res.attributes.contains(&attr("success", "false")));
+            assert!(res.attributes.contains(&attr("out_of_gas", "true")));
+            assert!(!PENDING_CALLBACK.exists(deps.as_ref().storage));
+
+            let failed = FAILED_CALLBACKS.load(deps.as_ref().storage, ("channel-0", 4)).unwrap();
+            assert_eq!(failed.address, "jake");
+            assert_eq!(failed.msg.sequence, 4);
+            assert_eq!(failed.error, "codespace: sdk, code: 11");
+        }
+
+        // a callback rejected by the controller is saved as well
+        {
+            dispatch_with(deps.as_mut(), None, None).unwrap();
+            after_send_packet(deps.as_mut(), send_packet_reply(5)).unwrap();
+            complete(deps.as_mut(), 5).unwrap();
+
+            let result = SubMsgResult::Err("codespace: wasm, code: 5".into());
+            let res = after_callback(deps.as_mut(), result).unwrap();
+            assert!(res.attributes.contains(&attr("out_of_gas", "false")));
+            assert!(FAILED_CALLBACKS.has(deps.as_ref().storage, ("channel-0", 5)));
+        }
+
+        // a successful callback is not saved
+        {
+            dispatch_with(deps.as_mut(), None, None).unwrap();
+            after_send_packet(deps.as_mut(), send_packet_reply(6)).unwrap();
+            complete(deps.as_mut(), 6).unwrap();
+
+            let result = SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data:   None,
+            });
+            let res = after_callback(deps.as_mut(), result).unwrap();
+            assert!(res.attributes.contains(&attr("success", "true")));
+            assert!(!FAILED_CALLBACKS.has(deps.as_ref().storage, ("channel-0", 6)));
+        }
+
+        // a failed callback can be retried, and is removed once retried
+        {
+            let res = retry_callback(deps.as_mut(), "channel-0".into(), 4).unwrap();
+            assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "jake".into(),
+                msg:           to_binary(&ControllerExecuteMsg::Ics999(CallbackMsg {
+                    endpoint:      endpoint.clone(),
+                    sequence:      4,
+                    outcome:       PacketOutcome::Timeout {},
+                    callback_data: Some(b"hello".into()),
+                }))
+                .unwrap(),
+                funds:         vec![],
+            }));
+            assert!(!FAILED_CALLBACKS.has(deps.as_ref().storage, ("channel-0", 4)));
+
+            assert!(retry_callback(deps.as_mut(), "channel-0".into(), 4).is_err());
+        }
+    }
+
+    #[test]
+    fn tracking_packets() {
+        let mut deps = mock_dependencies();
+
+        let endpoint = IbcEndpoint { port_id: "port-0".into(), channel_id: "channel-0".into() };
+        let mock_cfg = Config {
+            default_account_code_id:    1,
+            default_timeout_secs:       300,
+            max_timeout_secs:           86_400,
+            voucher_backend:            VoucherBackend::TokenFactory {
+                flavor: TokenFactoryFlavor::Osmosis,
+            },
+            default_callback_gas_limit: 1_000_000,
+            max_callback_gas_limit:     5_000_000,
+        };
+
+        CONFIG.save(deps.as_mut().storage, &mock_cfg).unwrap();
+        ACTIVE_CHANNELS.save(deps.as_mut().storage, "connection-0", &endpoint).unwrap();
+        CHANNEL_CONNECTIONS
+            .save(deps.as_mut().storage, "channel-0", &"connection-0".into())
+            .unwrap();
+
+        // a voucher that was received through the channel
+        let trace = TraceItem {
+            base_denom: "uatom".into(),
+            path:       vec![endpoint.clone()],
+        };
+        DENOM_TRACES.save(deps.as_mut().storage, "factory/voucher", &trace).unwrap();
+        VOUCHER_SUPPLIES
+            .save(deps.as_mut().storage, "factory/voucher", &Uint128::new(800))
+            .unwrap();
+
+        let actions = vec![
+            Action::Transfer {
+                denom:     "uosmo".into(),
+                amount:    Uint128::new(100),
+                recipient: None,
+            },
+            Action::Transfer {
+                denom:     "factory/voucher".into(),
+                amount:    Uint128::new(200),
+                recipient: None,
+            },
+            Action::RegisterAccount(RegisterOptions::Default {
+                salt: None,
+            }),
+        ];
+
+        let funds = [coin(100, "uosmo"), coin(200, "factory/voucher")];
+        let dispatch_as = |deps: DepsMut, sender: &str| {
+            dispatch(
+                deps,
+                mock_env(),
+                mock_info(sender, &funds),
+                "connection-0".into(),
+                actions.clone(),
+                None,
+                None,
+                false,
+                None,
+                None,
+            )
+            .unwrap()
+        };
+
+        // the packet is sent in a submessage, whose reply carries its sequence
+        {
+            let res = dispatch_as(deps.as_mut(), "larry");
+            let send_msg = res.messages.last().unwrap();
+            assert_eq!(send_msg.id, AFTER_SEND_PACKET);
+            assert_eq!(send_msg.reply_on, ReplyOn::Success);
+
+            // nothing is recorded until then
+            assert!(query::packet(deps.as_ref(), "channel-0".into(), 1).is_err());
+        }
+
+        // the sequence must be found in the send_packet event
+        {
+            let result = SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data:   None,
+            });
+            let err = after_send_packet(deps.as_mut(), result).unwrap_err();
+            assert_eq!(err, Error::SequenceMissing {
+                channel_id: "channel-0".into(),
+            });
+        }
+
+        // the records are saved under the sequence assigned by the IBC module,
+        // which this contract has no say in
+        dispatch_as(deps.as_mut(), "larry");
+        after_send_packet(deps.as_mut(), send_packet_reply(1)).unwrap();
+
+        for (sequence, sender) in [(2, "jake"), (3, "larry")] {
+            dispatch_as(deps.as_mut(), sender);
+            after_send_packet(deps.as_mut(), send_packet_reply(sequence)).unwrap();
+        }
+
+        let timeout = IbcTimeout::with_timestamp(mock_env().block.time.plus_seconds(300));
+
+        let packet = query::packet(deps.as_ref(), "channel-0".into(), 1).unwrap();
+        assert_eq!(packet, PacketInfo {
+            channel_id:    "channel-0".into(),
+            sequence:      1,
+            connection_id: "connection-0".into(),
+            controller:    "larry".into(),
+            actions:       vec!["transfer".into(), "transfer".into(), "register_account".into()],
+            escrowed:      vec![coin(100, "uosmo")],
+            burned:        vec![coin(200, "factory/voucher")],
+            timeout:       timeout.clone(),
+            status:        PacketStatus::Pending {},
+        });
+
+        let pending = |deps: Deps, controller: &str| {
+            query::pending_packets(deps, controller.into(), None, None)
+                .unwrap()
+                .into_iter()
+                .map(|packet| packet.sequence)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(pending(deps.as_ref(), "larry"), vec![1, 3]);
+        assert_eq!(pending(deps.as_ref(), "jake"), vec![2]);
+
+        // the voucher's trace changes while the packet is in-flight, e.g. it is
+        // remapped. this doesn't affect the refunds
+        DENOM_TRACES
+            .save(deps.as_mut().storage, "factory/voucher", &TraceItem::new("uatom"))
+            .unwrap();
+
+        // once the packet is complete, it is no longer pending
+        let packet = IbcPacket::new(
+            to_binary(&PacketData {
+                controller:    "larry".into(),
+                actions:       actions.clone(),
+                traces:        vec![],
+                forward_ack:   None,
+                allow_failure: false,
+            })
+            .unwrap(),
+            endpoint,
+            IbcEndpoint { port_id: "port-1".into(), channel_id: "channel-1".into() },
+            1,
+            timeout,
+        );
+        let packet_data = from_binary(&packet.data).unwrap();
+        let mut attrs = vec![];
+        complete_packet(
+            deps.as_mut(),
+            &mock_env(),
+            &packet,
+            packet_data,
+            PacketOutcome::Timeout {},
+            &mut vec![],
+            &mut attrs,
+        )
+        .unwrap();
+
+        // the escrowed coins are released, and the burned vouchers minted
+        assert_eq!(attrs, vec![
+            attr("coin", "100uosmo"),
+            attr("action", "release"),
+            attr("coin", "200factory/voucher"),
+            attr("action", "mint"),
+        ]);
+        assert!(!COMMITMENTS.has(deps.as_ref().storage, ("channel-0", 1)));
+
+        let packet = query::packet(deps.as_ref(), "channel-0".into(), 1).unwrap();
+        assert_eq!(packet.status, PacketStatus::Complete {
+            outcome: "timeout".into(),
+        });
+
+        assert_eq!(pending(deps.as_ref(), "larry"), vec![3]);
+    }
+
+    #[test]
+    fn resolving_traces() {
+        let querier = mock_querier(
+            "/ibc.applications.transfer.v1.Query/DenomTrace",
+            r#"{"denom_trace":{"path":"transfer/channel-0","base_denom":"uatom"}}"#,
+        );
+        let mut deps = mock_dependencies();
+        let deps_with = |querier| Deps {
+            storage: &deps.storage,
+            api:     &deps.api,
+            querier: QuerierWrapper::new(querier),
+        };
+
+        // the ICS-20 trace is resolved if the chain supports the query
+        {
+            let trace = trace_of(deps_with(&querier), "ibc/ABCD").unwrap();
+            assert_eq!(trace.base_denom, "uatom");
+            assert_eq!(trace.path.len(), 1);
+        }
+
+        // otherwise, the token is treated as native, instead of failing
+        {
+            let trace = trace_of(deps.as_ref(), "ibc/ABCD").unwrap();
+            assert_eq!(trace, TraceItem::new("ibc/ABCD"));
+
+            let trace = stored_trace_of(deps.as_ref().storage, "ibc/ABCD").unwrap();
+            assert_eq!(trace, TraceItem::new("ibc/ABCD"));
+        }
+
+        // traces of vouchers are stored
+        DENOM_TRACES
+            .save(deps.as_mut().storage, "factory/voucher", &TraceItem::new("uatom"))
+            .unwrap();
+        let trace = stored_trace_of(deps.as_ref().storage, "factory/voucher").unwrap();
+        assert_eq!(trace, TraceItem::new("uatom"));
+    }
+
     #[test]
     fn sending_packet() {
         // TODO
@@ -1,9 +1,13 @@
 use cosmwasm_std::IbcEndpoint;
 
 use {
-    crate::utils::Coins,
-    cosmwasm_std::{IbcOrder, Instantiate2AddressError, OverflowError, StdError},
+    crate::{msg::PauseScope, utils::Coins},
+    cosmwasm_std::{
+        Coin, IbcOrder, Instantiate2AddressError, OverflowError, StdError, Timestamp, Uint128,
+    },
+    cw_ownable::OwnershipError,
     cw_utils::{ParseReplyError, PaymentError},
+    ics999::ErrorCode,
 };
 
 #[derive(Debug, PartialEq, thiserror::Error)]
@@ -17,6 +21,9 @@ pub enum Error {
     #[error(transparent)]
     Instantiate2Address(#[from] Instantiate2AddressError),
 
+    #[error(transparent)]
+    Ownership(#[from] OwnershipError),
+
     #[error(transparent)]
     Payment(#[from] PaymentError),
 
@@ -32,15 +39,102 @@ pub enum Error {
     #[error("action queue cannot be empty")]
     EmptyActionQueue,
 
+    #[error("cannot release `{amount}` through channel `{channel_id}`, which only has `{escrowed}` in escrow")]
+    InsufficientEscrow {
+        channel_id: String,
+        escrowed:   Coin,
+        amount:     Coin,
+    },
+
+    #[error("no escrow or burn of `{coin}` was recorded for packet `{channel_id}/{sequence}`")]
+    CommitmentNotFound {
+        channel_id: String,
+        sequence:   u64,
+        coin:       Coin,
+    },
+
+    #[error("timeout of `{secs}` seconds exceeds the maximum `{max}`")]
+    TimeoutTooLong {
+        secs: u64,
+        max:  u64,
+    },
+
+    #[error("timeout timestamp `{timestamp}` is later than the latest allowed `{latest}`")]
+    TimeoutTooLate {
+        timestamp: Timestamp,
+        latest:    Timestamp,
+    },
+
+    #[error("callback gas limit `{limit}` exceeds the maximum `{max}`")]
+    CallbackGasLimitTooHigh {
+        limit: u64,
+        max:   u64,
+    },
+
     #[error("account factory failed to return instantiate data in its response")]
     FactoryResponseDataMissing,
 
-    #[error("cannot create voucher token because token create fee is non-zero")]
-    NonZeroTokenCreationFee,
+    #[error("denom creation pool has insufficient funds to pay the fee: required `{fee}`, available `{balance}`")]
+    InsufficientDenomCreationPool {
+        fee:     Coin,
+        balance: Coin,
+    },
+
+    #[error("native voucher `{denom}` is not supported by the cw20 voucher backend")]
+    NativeVoucherUnsupported {
+        denom: String,
+    },
 
     #[error("unauthorized")]
     Unauthorized,
 
+    #[error("{scope} is paused")]
+    Paused {
+        scope: PauseScope,
+    },
+
+    #[error("rate limit period must be non-zero")]
+    ZeroRatePeriod,
+
+    #[error("{direction} of `{denom}` on connection `{connection_id}` exceeds rate limit: quota `{quota}`, attempted `{attempted}`")]
+    RateLimitExceeded {
+        connection_id: String,
+        denom:         String,
+        direction:     String,
+        quota:         Uint128,
+        attempted:     Uint128,
+    },
+
+    #[error("the sequence of the packet sent on channel `{channel_id}` is missing from the send_packet event")]
+    SequenceMissing {
+        channel_id: String,
+    },
+
+    #[error("an owner must be provided when migrating from a version without one")]
+    OwnerMissing,
+
+    #[error("escrows must be provided when migrating with active channels")]
+    EscrowsMissing,
+
+    #[error("escrowed amount of `{denom}` is `{escrowed}`, exceeding the contract's balance `{balance}`")]
+    EscrowExceedsBalance {
+        denom:    String,
+        escrowed: Uint128,
+        balance:  Uint128,
+    },
+
+    #[error("cannot migrate from contract `{actual}`, expecting `{expected}`")]
+    IncorrectContract {
+        actual:   String,
+        expected: String,
+    },
+
+    #[error("cannot migrate from version `{from}` to older version `{to}`")]
+    Downgrade {
+        from: String,
+        to:   String,
+    },
+
     #[error("ICS-999 channel may not be closed")]
     UnexpectedChannelClosure,
 
@@ -72,6 +166,16 @@ pub enum Error {
         connection_id: String,
     },
 
+    #[error("the ICS-999 channel on connection `{connection_id}` has not been closed")]
+    ChannelNotClosed {
+        connection_id: String,
+    },
+
+    #[error("packets in flight on channel `{channel_id}` may be refunded from its escrow")]
+    PacketsInFlight {
+        channel_id: String,
+    },
+
     #[error("an interchain account already exists for endpoint `{endpoint:?}`, and controller `{controller}`")]
     AccountExists {
         endpoint:   IbcEndpoint,
@@ -83,6 +187,73 @@ pub enum Error {
         endpoint:   IbcEndpoint,
         controller: String,
     },
+
+    #[error("execute funds `{required}` exceed the amount delivered to the ICA earlier in the packet: `{delivered}`")]
+    ExecuteFundsNotDelivered {
+        required:  Coin,
+        delivered: Coin,
+    },
+
+    #[error("cw20 token `{denom}` can't be sent along with an execute; transfer it to the ICA instead")]
+    Cw20ExecuteFunds {
+        denom: String,
+    },
+
+    #[error("failed to resolve placeholder `{placeholder}`: {reason}")]
+    InvalidPlaceholder {
+        placeholder: String,
+        reason:      String,
+    },
+
+    #[error("assertion failed: {0}")]
+    AssertionFailed(String),
+
+    #[error("action `{index}` failed: {error}")]
+    ActionFailed {
+        index: u64,
+        code:  ErrorCode,
+        error: String,
+    },
+
+    #[error("no forward action is awaiting the outcome of packet `{sequence}` on channel `{channel_id}`")]
+    ForwardNotPending {
+        channel_id: String,
+        sequence:   u64,
+    },
+}
+
+impl Error {
+    /// Classify the error for the sender of a packet in which an action failed
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::Paused { .. } => ErrorCode::Paused,
+            Error::RateLimitExceeded { .. } => ErrorCode::RateLimited,
+            Error::TraceNotFound { .. } => ErrorCode::TraceNotFound,
+            Error::AccountNotFound { .. } => ErrorCode::AccountNotFound,
+            Error::AccountExists { .. } => ErrorCode::AccountExists,
+            Error::InvalidPlaceholder { .. } => ErrorCode::InvalidPlaceholder,
+            Error::AssertionFailed(_) => ErrorCode::AssertionFailed,
+            Error::QuerySystem(_) | Error::QueryContract(_) => ErrorCode::QueryFailed,
+            Error::ExecuteFundsNotDelivered { .. }
+            | Error::InsufficientDenomCreationPool { .. }
+            | Error::InsufficientEscrow { .. } => ErrorCode::InsufficientFunds,
+            Error::ActionFailed { code, .. } => code.clone(),
+            _ => ErrorCode::Other,
+        }
+    }
+}
+
+/// Classify an error message redacted by wasmd, which only includes the
+/// codespace and code of the error, e.g. `codespace: wasm, code: 5`.
+pub fn redacted_error_code(error: &str) -> ErrorCode {
+    match error {
+        // the ICA or another contract returned an error upon execution or
+        // instantiation
+        "codespace: wasm, code: 4" | "codespace: wasm, code: 5" => ErrorCode::ContractError,
+        "codespace: sdk, code: 5" => ErrorCode::InsufficientFunds,
+        "codespace: sdk, code: 11" => ErrorCode::OutOfGas,
+        _ => ErrorCode::Other,
+    }
 }
 
 pub(crate) type Result<T> = core::result::Result<T, Error>;
@@ -0,0 +1,199 @@
+use {
+    crate::{error::Result, msg::PacketFee, state::PACKET_FEES, utils::Coins, CustomMsg},
+    cosmwasm_std::{
+        attr, Addr, Attribute, BankMsg, Coin, CosmosMsg, IbcPacket, OverflowError, StdResult,
+        Storage,
+    },
+};
+
+impl PacketFee {
+    /// The total amount of coins to be escrowed for the packet.
+    pub fn total(&self) -> std::result::Result<Coins, OverflowError> {
+        sum(&[&self.recv_fee, &self.ack_fee, &self.timeout_fee])
+    }
+}
+
+/// Escrow the relayer fees for a packet that has been sent.
+///
+/// The fee coins are expected to have been sent to the contract along with the
+/// dispatch message.
+pub fn escrow_fee(
+    store:      &mut dyn Storage,
+    channel_id: &str,
+    sequence:   u64,
+    fee:        &PacketFee,
+) -> StdResult<()> {
+    PACKET_FEES.save(store, (channel_id, sequence), fee)
+}
+
+/// Pay the relayer and refund the unused fees to the sender, once the packet
+/// has been either acknowledged or timed out.
+pub fn settle_fee(
+    store:     &mut dyn Storage,
+    packet:    &IbcPacket,
+    relayer:   &Addr,
+    sender:    &str,
+    timed_out: bool,
+    msgs:      &mut Vec<CosmosMsg<CustomMsg>>,
+    attrs:     &mut Vec<Attribute>,
+) -> Result<()> {
+    let key = (packet.src.channel_id.as_str(), packet.sequence);
+
+    let Some(fee) = PACKET_FEES.may_load(store, key)? else {
+        return Ok(());
+    };
+
+    PACKET_FEES.remove(store, key);
+
+    let (payment, refund) = if timed_out {
+        (sum(&[&fee.timeout_fee])?, sum(&[&fee.recv_fee, &fee.ack_fee])?)
+    } else {
+        (sum(&[&fee.recv_fee, &fee.ack_fee])?, sum(&[&fee.timeout_fee])?)
+    };
+
+    attrs.push(attr("fee_paid", payment.to_string()));
+    attrs.push(attr("fee_refunded", refund.to_string()));
+
+    if !payment.is_empty() {
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: relayer.into(),
+            amount:     payment.into(),
+        }));
+    }
+
+    if !refund.is_empty() {
+        msgs.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: sender.into(),
+            amount:     refund.into(),
+        }));
+    }
+
+    Ok(())
+}
+
+// zero amounts are skipped, as they can't be sent in a bank message
+fn sum(coin_vecs: &[&Vec<Coin>]) -> std::result::Result<Coins, OverflowError> {
+    let mut coins = Coins::empty();
+
+    for coin in coin_vecs.iter().flat_map(|coin_vec| coin_vec.iter()) {
+        if !coin.amount.is_zero() {
+            coins.add(coin.clone())?;
+        }
+    }
+
+    Ok(coins)
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coin, coins, testing::mock_dependencies, Binary, IbcEndpoint, IbcTimeout, Timestamp,
+    };
+
+    use super::*;
+
+    fn mock_packet(sequence: u64) -> IbcPacket {
+        IbcPacket::new(
+            Binary::default(),
+            IbcEndpoint {
+                port_id:    "wasm.one-core".into(),
+                channel_id: "channel-0".into(),
+            },
+            IbcEndpoint {
+                port_id:    "wasm.counterparty".into(),
+                channel_id: "channel-1".into(),
+            },
+            sequence,
+            IbcTimeout::with_timestamp(Timestamp::from_seconds(0)),
+        )
+    }
+
+    #[test]
+    fn settling_fee() {
+        let mut deps = mock_dependencies();
+
+        let fee = PacketFee {
+            recv_fee:    vec![coin(100, "uatom"), coin(0, "uosmo")],
+            ack_fee:     coins(50, "uatom"),
+            timeout_fee: coins(20, "uosmo"),
+        };
+
+        assert_eq!(fee.total().unwrap(), Coins::from(vec![coin(150, "uatom"), coin(20, "uosmo")]));
+
+        // acknowledged: recv and ack fees to the relayer, timeout fee refunded
+        {
+            escrow_fee(&mut deps.storage, "channel-0", 1, &fee).unwrap();
+
+            let mut msgs = vec![];
+            settle_fee(
+                &mut deps.storage,
+                &mock_packet(1),
+                &Addr::unchecked("relayer"),
+                "larry",
+                false,
+                &mut msgs,
+                &mut vec![],
+            )
+            .unwrap();
+
+            assert_eq!(msgs, vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "relayer".into(),
+                    amount:     coins(150, "uatom"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "larry".into(),
+                    amount:     coins(20, "uosmo"),
+                }),
+            ]);
+            assert!(!PACKET_FEES.has(&deps.storage, ("channel-0", 1)));
+        }
+
+        // timed out: timeout fee to the relayer, recv and ack fees refunded
+        {
+            escrow_fee(&mut deps.storage, "channel-0", 2, &fee).unwrap();
+
+            let mut msgs = vec![];
+            settle_fee(
+                &mut deps.storage,
+                &mock_packet(2),
+                &Addr::unchecked("relayer"),
+                "larry",
+                true,
+                &mut msgs,
+                &mut vec![],
+            )
+            .unwrap();
+
+            assert_eq!(msgs, vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "relayer".into(),
+                    amount:     coins(20, "uosmo"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "larry".into(),
+                    amount:     coins(150, "uatom"),
+                }),
+            ]);
+        }
+
+        // no fee was escrowed: nothing to do
+        {
+            let mut msgs = vec![];
+            settle_fee(
+                &mut deps.storage,
+                &mock_packet(3),
+                &Addr::unchecked("relayer"),
+                "larry",
+                false,
+                &mut msgs,
+                &mut vec![],
+            )
+            .unwrap();
+
+            assert!(msgs.is_empty());
+        }
+    }
+}
@@ -0,0 +1,756 @@
+use {
+    crate::{
+        controller::complete_packet,
+        error::{Error, Result},
+        state::{
+            CHANNEL_CONNECTIONS, CONFIG, EARLY_FORWARD_ACKS, FAILED_FORWARD_ACKS, PENDING_ACKS,
+        },
+        timeout::resolve_timeout,
+        utils::StorageBuffer,
+        CustomMsg,
+    },
+    cosmwasm_schema::cw_serde,
+    cosmwasm_std::{
+        from_slice, to_binary, Coin, CosmosMsg, DepsMut, Env, IbcEndpoint, IbcMsg, IbcPacket,
+        IbcReceiveResponse, MessageInfo, Response, StdResult, Storage, Uint128,
+    },
+    ics999::{Action, ActionResult, ForwardAck, PacketAck, PacketData, PacketOutcome},
+};
+
+/// Where a packet forwarded by this chain came from
+#[cw_serde]
+pub struct ForwardOrigin {
+    /// This chain's endpoint of the channel the original packet arrived through
+    pub endpoint: IbcEndpoint,
+
+    /// Sequence of the original packet
+    pub sequence: u64,
+
+    /// Controller of the original packet
+    pub controller: String,
+}
+
+/// A packet that has been acknowledged, but whose outcome is incomplete until
+/// the outcomes of the packets forwarded on its behalf are relayed back
+#[cw_serde]
+pub struct PendingAck {
+    pub packet:  IbcPacket,
+    pub outcome: PacketOutcome,
+}
+
+/// The controller on whose behalf the destination chain executes forwarded
+/// actions. Namespaced by the channel the original packet arrived through, so
+/// that it can't collide with an address on this chain.
+pub fn forwarded_controller(endpoint: &IbcEndpoint, controller: &str) -> String {
+    format!("{}/{controller}", endpoint.channel_id)
+}
+
+/// All tokens transferred by the actions, including those in `Forward` actions
+pub fn transfers(actions: &[Action]) -> Vec<Coin> {
+    actions
+        .iter()
+        .flat_map(|action| match action {
+            Action::Transfer {
+                denom,
+                amount,
+                ..
+            } => vec![Coin::new(amount.u128(), denom)],
+            Action::Forward {
+                actions,
+                ..
+            } => transfers(actions),
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// The denoms and amounts of all tokens transferred by the actions, including
+/// those in `Forward` actions, with the denoms mutable so that they can be
+/// translated to those on another chain
+pub fn transfers_mut(actions: &mut [Action]) -> Vec<(&mut String, Uint128)> {
+    actions
+        .iter_mut()
+        .flat_map(|action| match action {
+            Action::Transfer {
+                denom,
+                amount,
+                ..
+            } => vec![(denom, *amount)],
+            Action::Forward {
+                actions,
+                ..
+            } => transfers_mut(actions),
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Whether the packet's outcome is waiting for the outcomes of forwarded
+/// packets to be relayed back
+pub fn is_pending(outcome: &PacketOutcome) -> bool {
+    let PacketOutcome::Success(results) = outcome else {
+        return false;
+    };
+
+    results.iter().any(|result| {
+        matches!(result, ActionResult::Forward {
+            outcome: None,
+            ..
+        })
+    })
+}
+
+/// The tokens to be refunded given the packet's outcome: all of them if the
+/// packet failed or timed out, otherwise those in actions that failed, and in
+/// `Forward` actions whose forwarded packets (or any packets forwarded further
+/// on) failed or timed out.
+///
+/// Results map one-to-one to actions, as a successful packet returns exactly
+/// one result per action.
+pub fn refunded_transfers(actions: &[Action], outcome: &PacketOutcome) -> Vec<Coin> {
+    let PacketOutcome::Success(results) = outcome else {
+        return transfers(actions);
+    };
+
+    actions
+        .iter()
+        .zip(results)
+        .flat_map(|(action, result)| match (action, result) {
+            (
+                Action::Forward {
+                    actions,
+                    ..
+                },
+                ActionResult::Forward {
+                    outcome: Some(outcome),
+                    ..
+                },
+            ) => refunded_transfers(actions, outcome),
+            (action, ActionResult::Failed { .. }) => transfers(std::slice::from_ref(action)),
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Fill in the outcome of a forwarded packet in the results of the packet that
+/// forwarded it. Returns false if there is no forward action awaiting it.
+fn fill_forward_outcome(outcome: &mut PacketOutcome, ack: ForwardAck) -> bool {
+    let PacketOutcome::Success(results) = outcome else {
+        return false;
+    };
+
+    let slot = results.iter_mut().find_map(|result| match result {
+        ActionResult::Forward {
+            channel_id,
+            sequence,
+            outcome: slot @ None,
+        } if *channel_id == ack.forwarded_channel_id && *sequence == ack.forwarded_sequence => {
+            Some(slot)
+        },
+        _ => None,
+    });
+
+    let Some(slot) = slot else {
+        return false;
+    };
+
+    *slot = Some(ack.outcome);
+
+    true
+}
+
+/// If the packet forwarded some of its actions, and their outcomes are not all
+/// known yet, save it until they are relayed back. Otherwise, return the
+/// complete outcome.
+pub fn defer_completion(
+    store:   &mut dyn Storage,
+    packet:  &IbcPacket,
+    outcome: PacketOutcome,
+) -> StdResult<Option<PacketOutcome>> {
+    let mut outcome = outcome;
+
+    if !is_pending(&outcome) {
+        return Ok(Some(outcome));
+    }
+
+    let key = (packet.src.channel_id.as_str(), packet.sequence);
+
+    // outcomes relayed back before the packet was acknowledged. ones that don't
+    // match any of the forward actions are ignored, so that they can't prevent
+    // the packet from being acknowledged
+    for ack in EARLY_FORWARD_ACKS.may_load(store, key)?.unwrap_or_default() {
+        fill_forward_outcome(&mut outcome, ack);
+    }
+
+    EARLY_FORWARD_ACKS.remove(store, key);
+
+    if !is_pending(&outcome) {
+        return Ok(Some(outcome));
+    }
+
+    PENDING_ACKS.save(store, key, &PendingAck {
+        packet: packet.clone(),
+        outcome,
+    })?;
+
+    Ok(None)
+}
+
+/// Compose the message relaying the outcome of a forwarded packet back to the
+/// chain it was forwarded from.
+pub fn send_forward_ack(
+    store:       &mut dyn Storage,
+    env:         &Env,
+    channel_id:  &str,
+    packet_data: PacketData,
+) -> Result<CosmosMsg<CustomMsg>> {
+    let cfg = CONFIG.load(store)?;
+    let connection_id = CHANNEL_CONNECTIONS.load(store, channel_id)?;
+    let timeout = resolve_timeout(store, &env.block, &cfg, &connection_id, None)?;
+
+    Ok(IbcMsg::SendPacket {
+        channel_id: channel_id.into(),
+        data:       to_binary(&packet_data)?,
+        timeout,
+    }
+    .into())
+}
+
+/// Relay the outcome of a forwarded packet back again, after the counterparty
+/// rejected it.
+pub fn retry_forward_ack(
+    deps:       DepsMut,
+    env:        Env,
+    info:       MessageInfo,
+    channel_id: String,
+    sequence:   u64,
+) -> Result<Response<CustomMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let key = (channel_id.as_str(), sequence);
+    let failed = FAILED_FORWARD_ACKS.load(deps.storage, key)?;
+    FAILED_FORWARD_ACKS.remove(deps.storage, key);
+
+    let msg = send_forward_ack(deps.storage, &env, &channel_id, failed.packet_data)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "retry_forward_ack")
+        .add_attribute("channel_id", channel_id)
+        .add_attribute("sequence", sequence.to_string())
+        .add_message(msg))
+}
+
+/// Receive the outcome of a packet that the counterparty forwarded on behalf of
+/// a packet sent by this chain.
+///
+/// Once the outcomes of all packets forwarded on behalf of the original packet
+/// are known, the original packet is completed: tokens are refunded as needed,
+/// and the controller is called back (or the outcome is relayed further back,
+/// if the original packet was itself forwarded).
+///
+/// If the outcome can't be received, e.g. because the tokens can't be refunded,
+/// it is rejected with an error ack, and none of its state changes are written.
+/// The counterparty holds onto it, to be relayed again once the issue is fixed.
+pub fn receive_forward_ack(
+    deps:   DepsMut,
+    env:    Env,
+    packet: IbcPacket,
+    ack:    ForwardAck,
+) -> Result<IbcReceiveResponse<CustomMsg>> {
+    let res = IbcReceiveResponse::new()
+        .add_attribute("method", "receive_forward_ack")
+        .add_attribute("channel_id", &packet.dest.channel_id)
+        .add_attribute("sequence", ack.sequence.to_string())
+        .add_attribute("forwarded_outcome", ack.outcome.ty());
+
+    let mut buffer = StorageBuffer::new(deps.storage);
+    let buffered_deps = DepsMut {
+        storage: &mut buffer,
+        api:     deps.api,
+        querier: deps.querier,
+    };
+
+    match fill_in_forward_ack(buffered_deps, env, packet, ack, res.clone()) {
+        Ok(res) => {
+            buffer.commit();
+            Ok(res)
+        },
+        Err(err) => Ok(res.add_attribute("error", err.to_string()).set_ack(to_binary(
+            &PacketAck::Error {
+                index: None,
+                code:  err.code(),
+                error: err.to_string(),
+            },
+        )?)),
+    }
+}
+
+fn fill_in_forward_ack(
+    deps:   DepsMut,
+    env:    Env,
+    packet: IbcPacket,
+    ack:    ForwardAck,
+    res:    IbcReceiveResponse<CustomMsg>,
+) -> Result<IbcReceiveResponse<CustomMsg>> {
+    let key = (packet.dest.channel_id.as_str(), ack.sequence);
+
+    let res = res.set_ack(to_binary(&PacketAck::Success(vec![]))?);
+
+    // the original packet hasn't been acknowledged yet, which can happen if
+    // the relayers deliver the packets out of order. hold onto the outcome
+    // until it is
+    let Some(mut pending) = PENDING_ACKS.may_load(deps.storage, key)? else {
+        let mut acks = EARLY_FORWARD_ACKS.may_load(deps.storage, key)?.unwrap_or_default();
+        acks.push(ack);
+        EARLY_FORWARD_ACKS.save(deps.storage, key, &acks)?;
+
+        return Ok(res.add_attribute("complete", "false"));
+    };
+
+    let err = Error::ForwardNotPending {
+        channel_id: ack.forwarded_channel_id.clone(),
+        sequence:   ack.forwarded_sequence,
+    };
+
+    if !fill_forward_outcome(&mut pending.outcome, ack) {
+        return Err(err);
+    }
+
+    if is_pending(&pending.outcome) {
+        PENDING_ACKS.save(deps.storage, key, &pending)?;
+
+        return Ok(res.add_attribute("complete", "false"));
+    }
+
+    PENDING_ACKS.remove(deps.storage, key);
+
+    let mut msgs = vec![];
+    let mut attrs = vec![];
+
+    let packet_data = from_slice(&pending.packet.data)?;
+    let callback = complete_packet(
+        deps,
+        &env,
+        &pending.packet,
+        packet_data,
+        pending.outcome,
+        &mut msgs,
+        &mut attrs,
+    )?;
+
+    Ok(res
+        .add_attribute("complete", "true")
+        .add_attributes(attrs)
+        .add_messages(msgs)
+        .add_submessages(callback))
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coin, from_binary,
+        testing::{mock_dependencies, mock_env, mock_info},
+        Addr, BankMsg, Binary, IbcTimeout, WasmMsg,
+    };
+    use ics999::{CallbackMsg, ControllerExecuteMsg, ErrorCode};
+
+    use super::*;
+    use crate::{
+        controller::packet_lifecycle_complete,
+        msg::{Config, TokenFactoryFlavor, VoucherBackend},
+        state::{DENOM_TRACES, ESCROWS, FORWARDS, VOUCHER_SUPPLIES},
+        transfer::TraceItem,
+    };
+
+    fn transfer(denom: &str, amount: u128) -> Action {
+        Action::Transfer {
+            denom:     denom.into(),
+            amount:    Uint128::new(amount),
+            recipient: None,
+        }
+    }
+
+    fn forward_result(sequence: u64, outcome: Option<PacketOutcome>) -> ActionResult {
+        ActionResult::Forward {
+            channel_id: "channel-1".into(),
+            sequence,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn determining_refunds() {
+        let actions = vec![
+            transfer("uatom", 100),
+            Action::Forward {
+                connection_id: "connection-1".into(),
+                actions:       vec![transfer("uosmo", 200), Action::Forward {
+                    connection_id: "connection-2".into(),
+                    actions:       vec![transfer("ujuno", 300)],
+                }],
+            },
+            Action::Execute {
+                msg:   Binary::default(),
+                funds: vec![],
+            },
+        ];
+
+        assert_eq!(transfers(&actions), vec![
+            coin(100, "uatom"),
+            coin(200, "uosmo"),
+            coin(300, "ujuno"),
+        ]);
+
+        // the packet failed: everything is refunded
+        let outcome = PacketOutcome::Failed("codespace: wasm, code: 5".into());
+        assert!(!is_pending(&outcome));
+        assert_eq!(refunded_transfers(&actions, &outcome), transfers(&actions));
+
+        let results = |forward_outcome| {
+            PacketOutcome::Success(vec![
+                ActionResult::Transfer {
+                    denom:              "uatom".into(),
+                    new_token:          false,
+                    recipient:          "larry".into(),
+                    denom_creation_fee: vec![],
+                },
+                forward_result(1, forward_outcome),
+                ActionResult::Execute {
+                    data: None,
+                },
+            ])
+        };
+
+        // the forwarded packet's outcome is not yet known
+        let outcome = results(None);
+        assert!(is_pending(&outcome));
+        assert!(refunded_transfers(&actions, &outcome).is_empty());
+
+        // the forwarded packet timed out: the tokens forwarded are refunded
+        let outcome = results(Some(PacketOutcome::Timeout {}));
+        assert!(!is_pending(&outcome));
+        assert_eq!(refunded_transfers(&actions, &outcome), vec![
+            coin(200, "uosmo"),
+            coin(300, "ujuno"),
+        ]);
+
+        // the packet forwarded by the forwarded packet failed: only the tokens
+        // forwarded the second time are refunded
+        let outcome = results(Some(PacketOutcome::Success(vec![
+            ActionResult::Transfer {
+                denom:              "uosmo".into(),
+                new_token:          false,
+                recipient:          "larry".into(),
+                denom_creation_fee: vec![],
+            },
+            forward_result(2, Some(PacketOutcome::Failed("codespace: wasm, code: 5".into()))),
+        ])));
+        assert_eq!(refunded_transfers(&actions, &outcome), vec![coin(300, "ujuno")]);
+
+        // the packet allows failure, and some actions failed: the tokens of the
+        // failed transfer and forward are refunded
+        let failed = || ActionResult::Failed {
+            code:  ErrorCode::ContractError,
+            error: "codespace: wasm, code: 5".into(),
+        };
+        let outcome = PacketOutcome::Success(vec![failed(), failed(), ActionResult::Execute {
+            data: None,
+        }]);
+        assert!(!is_pending(&outcome));
+        assert_eq!(refunded_transfers(&actions, &outcome), transfers(&actions));
+    }
+
+    fn endpoint(channel_id: &str) -> IbcEndpoint {
+        IbcEndpoint {
+            port_id:    "wasm.core".into(),
+            channel_id: channel_id.into(),
+        }
+    }
+
+    fn mock_packet(src: &str, sequence: u64, packet_data: &PacketData) -> IbcPacket {
+        IbcPacket::new(
+            to_binary(packet_data).unwrap(),
+            endpoint(src),
+            endpoint("channel-9"),
+            sequence,
+            IbcTimeout::with_timestamp(mock_env().block.time.plus_seconds(300)),
+        )
+    }
+
+    fn setup_chain(deps: &mut DepsMut, channel_ids: &[&str]) {
+        CONFIG
+            .save(deps.storage, &Config {
+                default_account_code_id:    1,
+                default_timeout_secs:       300,
+                max_timeout_secs:           86_400,
+                voucher_backend:            VoucherBackend::TokenFactory {
+                    flavor: TokenFactoryFlavor::Osmosis,
+                },
+                default_callback_gas_limit: 1_000_000,
+                max_callback_gas_limit:     5_000_000,
+            })
+            .unwrap();
+
+        for (i, channel_id) in channel_ids.iter().enumerate() {
+            let connection_id = format!("connection-{i}");
+            CHANNEL_CONNECTIONS.save(deps.storage, channel_id, &connection_id).unwrap();
+        }
+    }
+
+    #[test]
+    fn relaying_forwarded_outcome() {
+        let failed = PacketOutcome::Failed("codespace: wasm, code: 5".into());
+
+        // on the intermediate chain, the forwarded packet fails. the vouchers
+        // minted upon receiving the tokens are burned, and the outcome is
+        // relayed back through the channel the original packet arrived from
+        let relay_packet_data = {
+            let mut deps = mock_dependencies();
+            setup_chain(&mut deps.as_mut(), &["channel-0", "channel-1"]);
+
+            let voucher = "factory/cosmos2contract/voucher";
+            DENOM_TRACES
+                .save(deps.as_mut().storage, voucher, &TraceItem {
+                    base_denom: "uatom".into(),
+                    path:       vec![endpoint("channel-0")],
+                })
+                .unwrap();
+            FORWARDS
+                .save(deps.as_mut().storage, ("channel-1", 1), &ForwardOrigin {
+                    endpoint:   endpoint("channel-0"),
+                    sequence:   7,
+                    controller: "larry".into(),
+                })
+                .unwrap();
+
+            // the vouchers were minted upon receiving them, then escrowed when
+            // they were forwarded
+            VOUCHER_SUPPLIES.save(deps.as_mut().storage, voucher, &Uint128::new(100)).unwrap();
+            ESCROWS
+                .save(deps.as_mut().storage, ("channel-1", voucher), &Uint128::new(100))
+                .unwrap();
+
+            let packet = mock_packet("channel-1", 1, &PacketData {
+                controller:    forwarded_controller(&endpoint("channel-0"), "larry"),
+                actions:       vec![transfer(voucher, 100)],
+                traces:        vec![],
+                forward_ack:   None,
+                allow_failure: false,
+            });
+            let ack = to_binary(&PacketAck::Failed("codespace: wasm, code: 5".into())).unwrap();
+
+            let res = packet_lifecycle_complete(
+                deps.as_mut(),
+                mock_env(),
+                packet,
+                Some(ack),
+                Addr::unchecked("relayer"),
+            )
+            .unwrap();
+            assert_eq!(res.messages.len(), 2);
+
+            let CosmosMsg::Ibc(IbcMsg::SendPacket { channel_id, data, .. }) = &res.messages[1].msg
+            else {
+                panic!("expecting a SendPacket msg");
+            };
+            assert_eq!(channel_id, "channel-0");
+            assert!(!FORWARDS.has(deps.as_ref().storage, ("channel-1", 1)));
+            assert!(!ESCROWS.has(deps.as_ref().storage, ("channel-1", voucher)));
+            assert!(!VOUCHER_SUPPLIES.has(deps.as_ref().storage, voucher));
+
+            let packet_data: PacketData = from_binary(data).unwrap();
+            assert_eq!(packet_data.forward_ack, Some(ForwardAck {
+                sequence:             7,
+                forwarded_channel_id: "channel-1".into(),
+                forwarded_sequence:   1,
+                outcome:              failed.clone(),
+            }));
+
+            packet_data
+        };
+
+        // on the sender chain, the original packet is acknowledged, but isn't
+        // complete until the forwarded packet's outcome is relayed back
+        let mut deps = mock_dependencies();
+        setup_chain(&mut deps.as_mut(), &["channel-0"]);
+
+        // the tokens were escrowed when the original packet was sent
+        ESCROWS.save(deps.as_mut().storage, ("channel-0", "uatom"), &Uint128::new(100)).unwrap();
+
+        let packet = mock_packet("channel-0", 7, &PacketData {
+            controller:    "larry".into(),
+            actions:       vec![Action::Forward {
+                connection_id: "connection-1".into(),
+                actions:         vec![transfer("uatom", 100)],
+            }],
+            traces:        vec![],
+            forward_ack:   None,
+            allow_failure: false,
+        });
+        let ack = to_binary(&PacketAck::Success(vec![forward_result(1, None)])).unwrap();
+
+        let res = packet_lifecycle_complete(
+            deps.as_mut(),
+            mock_env(),
+            packet,
+            Some(ack),
+            Addr::unchecked("relayer"),
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert!(PENDING_ACKS.has(deps.as_ref().storage, ("channel-0", 7)));
+
+        // the outcome is relayed back: the tokens are refunded, and the
+        // controller is called back
+        let relay_packet = IbcPacket::new(
+            to_binary(&relay_packet_data).unwrap(),
+            endpoint("channel-9"),
+            endpoint("channel-0"),
+            5,
+            IbcTimeout::with_timestamp(mock_env().block.time.plus_seconds(300)),
+        );
+        let forward_ack = relay_packet_data.forward_ack.unwrap();
+
+        let res = receive_forward_ack(deps.as_mut(), mock_env(), relay_packet, forward_ack)
+            .unwrap();
+        assert!(!PENDING_ACKS.has(deps.as_ref().storage, ("channel-0", 7)));
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "larry".into(),
+                amount:     vec![coin(100, "uatom")],
+            }),
+        );
+
+        let CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) = &res.messages[1].msg
+        else {
+            panic!("expecting a callback msg");
+        };
+        assert_eq!(contract_addr, "larry");
+        assert_eq!(
+            from_binary::<ControllerExecuteMsg>(msg).unwrap(),
+            ControllerExecuteMsg::Ics999(CallbackMsg {
+                endpoint:      endpoint("channel-0"),
+                sequence:      7,
+                outcome:       PacketOutcome::Success(vec![forward_result(1, Some(failed))]),
+                callback_data: None,
+            }),
+        );
+    }
+
+    #[test]
+    fn rejecting_forward_ack() {
+        let forward_ack = |forwarded_sequence: u64| ForwardAck {
+            sequence:             7,
+            forwarded_channel_id: "channel-1".into(),
+            forwarded_sequence,
+            outcome:              PacketOutcome::Timeout {},
+        };
+        let relay_packet_data = |ack: ForwardAck| PacketData {
+            controller:    "larry".into(),
+            actions:       vec![],
+            traces:        vec![],
+            forward_ack:   Some(ack),
+            allow_failure: false,
+        };
+
+        // on the sender chain, the original packet awaits the outcome of the
+        // packet forwarded with sequence 1
+        let mut deps = mock_dependencies();
+        setup_chain(&mut deps.as_mut(), &["channel-0"]);
+
+        let pending = PendingAck {
+            packet:  mock_packet("channel-0", 7, &PacketData {
+                controller:    "larry".into(),
+                actions:       vec![Action::Forward {
+                    connection_id: "connection-1".into(),
+                    actions:       vec![transfer("uatom", 100)],
+                }],
+                traces:        vec![],
+                forward_ack:   None,
+                allow_failure: false,
+            }),
+            outcome: PacketOutcome::Success(vec![forward_result(1, None)]),
+        };
+        PENDING_ACKS.save(deps.as_mut().storage, ("channel-0", 7), &pending).unwrap();
+
+        // an outcome that can't be filled in is rejected with an error ack,
+        // instead of failing the packet, which would have it relayed again and
+        // again. nothing is written
+        {
+            let ack = forward_ack(2);
+            let relay_packet = IbcPacket::new(
+                to_binary(&relay_packet_data(ack.clone())).unwrap(),
+                endpoint("channel-9"),
+                endpoint("channel-0"),
+                5,
+                IbcTimeout::with_timestamp(mock_env().block.time.plus_seconds(300)),
+            );
+
+            let res = receive_forward_ack(deps.as_mut(), mock_env(), relay_packet, ack).unwrap();
+            let err = Error::ForwardNotPending {
+                channel_id: "channel-1".into(),
+                sequence:   2,
+            };
+            assert_eq!(
+                from_binary::<PacketAck>(&res.acknowledgement).unwrap(),
+                PacketAck::Error {
+                    index: None,
+                    code:  err.code(),
+                    error: err.to_string(),
+                },
+            );
+            let unchanged = PENDING_ACKS.load(deps.as_ref().storage, ("channel-0", 7)).unwrap();
+            assert_eq!(unchanged, pending);
+        }
+
+        // on the intermediate chain, the rejected packet is held onto
+        let mut deps = mock_dependencies();
+        setup_chain(&mut deps.as_mut(), &["channel-0"]);
+        cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("owner")).unwrap();
+
+        let packet_data = relay_packet_data(forward_ack(2));
+        let ack = to_binary(&PacketAck::Error {
+            index: None,
+            code:  ErrorCode::Other,
+            error: "forwarded packet is not pending".into(),
+        })
+        .unwrap();
+
+        let res = packet_lifecycle_complete(
+            deps.as_mut(),
+            mock_env(),
+            mock_packet("channel-0", 5, &packet_data),
+            Some(ack),
+            Addr::unchecked("relayer"),
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+
+        let failed = FAILED_FORWARD_ACKS.load(deps.as_ref().storage, ("channel-0", 5)).unwrap();
+        assert_eq!(failed.packet_data, packet_data);
+
+        // only the admin can send it again
+        {
+            let info = mock_info("larry", &[]);
+            let err = retry_forward_ack(deps.as_mut(), mock_env(), info, "channel-0".into(), 5)
+                .unwrap_err();
+            assert!(matches!(err, Error::Ownership(_)));
+        }
+
+        let info = mock_info("owner", &[]);
+        let res =
+            retry_forward_ack(deps.as_mut(), mock_env(), info, "channel-0".into(), 5).unwrap();
+        assert!(!FAILED_FORWARD_ACKS.has(deps.as_ref().storage, ("channel-0", 5)));
+
+        let CosmosMsg::Ibc(IbcMsg::SendPacket { channel_id, data, .. }) = &res.messages[0].msg
+        else {
+            panic!("expecting a SendPacket msg");
+        };
+        assert_eq!(channel_id, "channel-0");
+        assert_eq!(from_binary::<PacketData>(data).unwrap(), packet_data);
+    }
+}
@@ -1,7 +1,8 @@
 use {
     crate::{
         error::{Error, Result},
-        state::ACTIVE_CHANNELS,
+        state::{ACTIVE_CHANNELS, CHANNEL_CONNECTIONS, CLOSED_CHANNELS},
+        CustomMsg,
     },
     cosmwasm_std::{
         DepsMut, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelOpenResponse,
@@ -42,10 +43,11 @@ pub fn open_connect(
     deps:                 DepsMut,
     channel:              &IbcChannel,
     counterparty_version: Option<&str>,
-) -> Result<IbcBasicResponse> {
+) -> Result<IbcBasicResponse<CustomMsg>> {
     validate_order_and_version(&channel.order, &channel.version, counterparty_version)?;
 
     ACTIVE_CHANNELS.save(deps.storage, &channel.connection_id, &channel.endpoint)?;
+    CHANNEL_CONNECTIONS.save(deps.storage, &channel.endpoint.channel_id, &channel.connection_id)?;
 
     Ok(IbcBasicResponse::new()
         .add_attribute("method", "open_connect")
@@ -95,7 +97,7 @@ fn assert_unique_channel(store: &dyn Storage, connection_id: &str) -> Result<()>
     Ok(())
 }
 
-pub fn close(msg: IbcChannelCloseMsg) -> Result<IbcBasicResponse> {
+pub fn close(deps: DepsMut, msg: IbcChannelCloseMsg) -> Result<IbcBasicResponse<CustomMsg>> {
     match msg {
         // we do not expect an ICS-999 channel to be closed
         IbcChannelCloseMsg::CloseInit {
@@ -111,12 +113,25 @@ pub fn close(msg: IbcChannelCloseMsg) -> Result<IbcBasicResponse> {
         // closed, but we think it's still open. To avoid this inconsistency,
         // we must let the tx go through.
         //
-        // We probably should delete the ACTIVE_CHANNEL, since the channel is
-        // now closed... However, as we're in a catastrophic situation that
-        // requires admin intervention anyways, let's leave this to the admin.
+        // We don't delete the ACTIVE_CHANNEL here, as we're in a catastrophic
+        // situation that requires admin intervention anyways. Instead, we mark
+        // the channel as closed, so that the admin may retire it and replace it
+        // with a new one (see the `recovery` module).
         IbcChannelCloseMsg::CloseConfirm {
-            ..
-        } => Ok(IbcBasicResponse::new()),
+            channel,
+        } => {
+            let active = ACTIVE_CHANNELS.may_load(deps.storage, &channel.connection_id)?;
+
+            if active.as_ref() == Some(&channel.endpoint) {
+                CLOSED_CHANNELS.save(deps.storage, &channel.connection_id, &channel.endpoint)?;
+            }
+
+            Ok(IbcBasicResponse::new()
+                .add_attribute("method", "close_confirm")
+                .add_attribute("connection_id", &channel.connection_id)
+                .add_attribute("port_id", &channel.endpoint.port_id)
+                .add_attribute("channel_id", &channel.endpoint.channel_id))
+        },
     }
 }
 
@@ -228,10 +243,32 @@ mod tests {
 
     #[test]
     fn rejecting_channel_close() {
-        let err = close(IbcChannelCloseMsg::CloseInit {
+        let mut deps = mock_dependencies();
+
+        let err = close(deps.as_mut(), IbcChannelCloseMsg::CloseInit {
             channel: mock_ibc_channel(),
         })
         .unwrap_err();
         assert_eq!(err, Error::UnexpectedChannelClosure);
     }
+
+    #[test]
+    fn confirming_channel_close() {
+        let mut deps = mock_dependencies();
+
+        let channel = mock_ibc_channel();
+
+        open_connect(deps.as_mut(), &channel, Some(ics999::VERSION)).unwrap();
+
+        close(deps.as_mut(), IbcChannelCloseMsg::CloseConfirm {
+            channel: channel.clone(),
+        })
+        .unwrap();
+
+        // the channel is marked as closed, but remains active until the admin
+        // retires it
+        let closed = CLOSED_CHANNELS.load(deps.as_ref().storage, &channel.connection_id).unwrap();
+        assert_eq!(closed, channel.endpoint);
+        assert!(ACTIVE_CHANNELS.has(deps.as_ref().storage, &channel.connection_id));
+    }
 }
@@ -1,30 +1,51 @@
 use {
+    super::placeholder::resolve_placeholders,
     crate::{
+        assertion::{assert_balance, assert_query_response},
+        controller::{save_sent_packet, send_packet, SentPacket},
         error::{Error, Result},
-        state::{ACCOUNTS, CONFIG, DENOM_TRACES},
-        transfer::{assert_free_denom_creation, construct_denom, into_proto_coin, TraceItem},
-        AFTER_ACTION,
+        forward::{forwarded_controller, transfers_mut, ForwardOrigin},
+        msg::{ExecuteMsg, VoucherBackend},
+        rate_limit::record_inflow,
+        state::{ACCOUNTS, CHANNEL_CONNECTIONS, CONFIG, DENOM_TRACES, SENDING_PACKET},
+        transfer::{
+            add_voucher_supply, cw20_voucher_instantiate_msg, deduct_escrow, mint_msgs,
+            parse_cw20_denom, pay_denom_creation_fee, send_msg, voucher_denom, TraceItem,
+        },
+        utils::Coins,
+        CustomMsg,
+        AFTER_ACTION, AFTER_ISOLATED_ACTION,
     },
     cosmwasm_schema::cw_serde,
     cosmwasm_std::{
-        from_binary, instantiate2_address, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut,
-        Empty, Env, IbcEndpoint, QueryRequest, Response, StdResult, Storage, SubMsg, Uint128,
-        WasmMsg, WasmQuery,
+        from_binary, instantiate2_address, to_binary, Addr, Binary, Coin, CosmosMsg, Deps, DepsMut,
+        Empty, Env, Event, IbcEndpoint, QueryRequest, Response, StdResult, Storage, SubMsg,
+        SubMsgResponse, Uint128, WasmMsg, WasmQuery,
     },
     cw_storage_plus::Item,
     cw_utils::parse_execute_response_data,
     ics999::{
-        Action, ActionResult, FactoryExecuteMsg, FactoryMsg, FactoryResponse, RegisterOptions,
-        Trace,
+        Action, ActionResult, Assertion, ErrorCode, FactoryExecuteMsg, FactoryMsg,
+        FactoryResponse, PacketAck, RegisterOptions, Trace,
     },
-    osmosis_std::types::osmosis::tokenfactory::v1beta1 as tokenfactory,
     sha2::{Digest, Sha256},
 };
 
 const HANDLER: Item<Handler> = Item::new("handler");
 
+/// Tokens received along with the packet
+struct ReceivedTokens {
+    denom:              String,
+    new_token:          bool,
+    denom_creation_fee: Vec<Coin>,
+
+    /// Messages delivering the tokens to the recipient. The last message is
+    /// the one that does the delivery.
+    msgs: Vec<CosmosMsg<CustomMsg>>,
+}
+
 /// An ICS-999 packet contains one or more `Action`'s that need to be executed
-/// one at a time and atomically.
+/// one at a time and, unless the packet allows failure, atomically.
 ///
 /// Handler is an object that contains necessary states and methods for
 /// executing the actions. It also implements serde traits so that it can be
@@ -33,39 +54,77 @@ const HANDLER: Item<Handler> = Item::new("handler");
 pub(super) struct Handler {
     counterparty_endpoint: IbcEndpoint,
     endpoint:              IbcEndpoint,
+    sequence:              u64,
     controller:            String,
     host:                  Option<Addr>,
     traces:                Vec<Trace>,
     action:                Option<Action>,
     pending_actions:       Vec<Action>,
     results:               Vec<ActionResult>,
+    allow_failure:         bool,
+
+    // whether any of the actions executed so far took effect, in which case,
+    // if the packet is atomic, it can only be aborted by failing it entirely
+    committed: bool,
+
+    // coins that `Execute` actions are to send to the ICA, which are yet to be
+    // delivered by `Transfer` actions
+    reserved: Coins,
+
+    // coins delivered for `Execute` actions to send to the ICA, which are held
+    // by the contract itself until then
+    held: Coins,
 }
 
 impl Handler {
+    #[allow(clippy::too_many_arguments)]
     pub fn create(
         store:                 &dyn Storage,
         counterparty_endpoint: IbcEndpoint,
         endpoint:              IbcEndpoint,
+        sequence:              u64,
         controller:            String,
         mut actions:           Vec<Action>,
         traces:                Vec<Trace>,
-    ) -> StdResult<Self> {
+        allow_failure:         bool,
+    ) -> Result<Self> {
         // load the controller's ICA host, which may or may not have already
         // been instantiated
         let host = ACCOUNTS.may_load(store, (&endpoint.port_id, &endpoint.channel_id, &controller))?;
 
+        let mut reserved = Coins::empty();
+        for action in &actions {
+            if let Action::Execute { funds, .. } = action {
+                for coin in funds {
+                    // a wasm execute can only carry native coins
+                    if parse_cw20_denom(&coin.denom).is_some() {
+                        return Err(Error::Cw20ExecuteFunds {
+                            denom: coin.denom.clone(),
+                        });
+                    }
+
+                    reserved.add(coin.clone())?;
+                }
+            }
+        }
+
         // reverse the actions, so that we can use pop() to grab the 1st action
         actions.reverse();
 
         Ok(Self {
             counterparty_endpoint,
             endpoint,
+            sequence,
             controller,
             host,
             traces,
             action:          None,
             pending_actions: actions,
             results:         vec![],
+            allow_failure,
+            committed:       false,
+            reserved,
+            held:            Coins::empty(),
         })
     }
 
@@ -84,25 +143,62 @@ impl Handler {
     /// Execute the next action in the queue. Saved the updated handler state.
     pub fn handle_next_action(
         mut self,
-        mut deps: DepsMut,
+        deps:     DepsMut,
         env:      Env,
-        response: Option<Response>,
-    ) -> Result<Response> {
-        let mut response = response.unwrap_or_else(|| self.default_handle_action_response());
+        response: Option<Response<CustomMsg>>,
+    ) -> Result<Response<CustomMsg>> {
+        let response = response.unwrap_or_else(|| self.default_handle_action_response());
 
         // grab the first action in the queue
-        self.action = self.pending_actions.pop();
-
         // if there is no more action to execute
         // delete handler state from contract store, return the results as data
         // in the response
-        let Some(action) = &self.action else {
+        let Some(action) = self.pending_actions.pop() else {
+            self.action = None;
             Handler::remove(deps.storage);
-            return Ok(response.set_data(to_binary(&self.results)?));
+
+            // coins still held once all actions are done, e.g. as the `Execute`
+            // actions they were held for failed, belong to the ICA
+            let mut response = response;
+            if let Some(host) = &self.host {
+                for coin in Vec::<Coin>::from(self.held.clone()) {
+                    response = response.add_message(send_msg(coin, host)?);
+                }
+            }
+
+            return Ok(response.set_data(to_binary(&PacketAck::Success(self.results))?));
         };
 
+        self.action = Some(action);
+        self.save(deps.storage)?;
+
+        // execute the action in a submessage of its own, so that if it fails,
+        // only its own state changes are reverted, and we learn why it failed
+        Ok(response.add_submessage(SubMsg::reply_always(
+            WasmMsg::Execute {
+                contract_addr: env.contract.address.into(),
+                msg:           to_binary(&ExecuteMsg::HandleAction {})?,
+                funds:         vec![],
+            },
+            AFTER_ISOLATED_ACTION,
+        )))
+    }
+
+    /// Execute the current action. Saves the updated handler state.
+    pub fn handle_action(
+        mut self,
+        mut deps:     DepsMut,
+        env:          Env,
+        mut response: Response<CustomMsg>,
+    ) -> Result<Response<CustomMsg>> {
+        let action = self.action.take().expect("missing active action");
+
+        // fill in values from the results of earlier actions
+        let action = resolve_placeholders(action, &self.results)?;
+        self.action = Some(action.clone());
+
         // convert the action to the appropriate msgs and event attributes
-        let response = match action.clone() {
+        let response = match action {
             Action::Transfer {
                 denom,
                 amount,
@@ -120,10 +216,28 @@ impl Handler {
 
             Action::Query(msg) => {
                 response = self.handle_query(response, deps.as_ref(), msg)?;
-                return self.handle_next_action(deps, env, Some(response));
+                return self.finish_action(deps, Some(response));
+            },
+
+            Action::QueryChain(request) => {
+                response = self.handle_query_chain(response, deps.as_ref(), request)?;
+                return self.finish_action(deps, Some(response));
+            },
+
+            Action::Assert(assertion) => {
+                response = self.handle_assert(response, deps.as_ref(), assertion)?;
+                return self.finish_action(deps, Some(response));
             },
 
-            Action::Execute(msg) => self.handle_execute(response, msg)?,
+            Action::Execute {
+                msg,
+                funds,
+            } => self.handle_execute(response, msg, funds)?,
+
+            Action::Forward {
+                connection_id,
+                actions,
+            } => self.handle_forward(response, deps.branch(), &env, connection_id, actions)?,
         };
 
         self.save(deps.storage)?;
@@ -131,26 +245,128 @@ impl Handler {
         Ok(response)
     }
 
-    fn handle_transfer(
-        &mut self,
-        mut response: Response,
-        deps:         DepsMut,
-        env:          Env,
-        src_denom:    String,
-        amount:       Uint128,
-        recipient:    Option<String>,
-    ) -> Result<Response> {
-        response = response.add_attribute("action", "transfer");
+    /// Save the results once the current action is done. We move on to the
+    /// next action once the submessage executing it returns.
+    pub fn finish_action(
+        self,
+        deps:     DepsMut,
+        response: Option<Response<CustomMsg>>,
+    ) -> Result<Response<CustomMsg>> {
+        self.save(deps.storage)?;
+        Ok(response.unwrap_or_else(|| self.default_handle_action_response()))
+    }
 
-        let mut trace: TraceItem = self
-            .traces
-            .iter()
-            .find(|trace| trace.denom == src_denom)
-            .ok_or(Error::TraceNotFound {
-                denom: src_denom,
-            })?
-            .into();
+    /// Move on to the next action once the submessage executing the current
+    /// one succeeded. Unless it's a query or an assertion, it took effect.
+    pub fn after_succeeded_action(
+        mut self,
+        deps: DepsMut,
+        env:  Env,
+    ) -> Result<Response<CustomMsg>> {
+        let action = self.action.as_ref().expect("missing active action");
+
+        if !matches!(action, Action::Query(_) | Action::QueryChain(_) | Action::Assert(_)) {
+            self.committed = true;
+        }
+
+        self.handle_next_action(deps, env, None)
+    }
+
+    /// Abort the packet, as the current action failed and the packet doesn't
+    /// allow failure. The action was executed in isolation, so its own state
+    /// changes have been reverted.
+    ///
+    /// If none of the earlier actions took effect, there is nothing else to
+    /// revert, so we ack the packet with the index of the failed action and
+    /// why it failed. Otherwise, only failing the packet entirely reverts
+    /// their effects, in which case wasmd redacts the error.
+    fn abort(
+        self,
+        store: &mut dyn Storage,
+        code:  ErrorCode,
+        error: String,
+    ) -> Result<Response<CustomMsg>> {
+        let index = self.results.len() as u64;
+
+        if self.committed {
+            return Err(Error::ActionFailed {
+                index,
+                code,
+                error,
+            });
+        }
+
+        Handler::remove(store);
+
+        let action = self.action.as_ref().expect("missing active action");
+
+        Ok(self
+            .default_handle_action_response()
+            .add_attribute("action_failed", action.ty())
+            .set_data(to_binary(&PacketAck::Error {
+                index: Some(index),
+                code,
+                error,
+            })?))
+    }
+
+    /// Record the failure of the current action, which was executed in
+    /// isolation, so all its state changes have been reverted, and move on to
+    /// the next action. If the packet doesn't allow failure, abort it instead.
+    ///
+    /// If it's an `Execute` action with funds, the funds held for it are
+    /// delivered to the ICA instead, and those yet to be delivered by later
+    /// `Transfer` actions are no longer held once they are.
+    pub fn after_failed_action(
+        mut self,
+        deps:  DepsMut,
+        env:   Env,
+        code:  ErrorCode,
+        error: String,
+    ) -> Result<Response<CustomMsg>> {
+        if !self.allow_failure {
+            return self.abort(deps.storage, code, error);
+        }
+
+        let mut response = self.default_handle_action_response();
 
+        let action = self.action.as_ref().expect("missing active action");
+
+        if let Action::Execute { funds, .. } = action {
+            for coin in funds {
+                let held = self.held.amount_of(&coin.denom).min(coin.amount);
+                if let (false, Some(host)) = (held.is_zero(), &self.host) {
+                    let held_coin = Coin::new(held.u128(), &coin.denom);
+                    self.held.deduct(held_coin.clone())?;
+                    response = response.add_message(send_msg(held_coin, host)?);
+                }
+
+                let reserved = self.reserved.amount_of(&coin.denom).min(coin.amount - held);
+                if !reserved.is_zero() {
+                    self.reserved.deduct(Coin::new(reserved.u128(), &coin.denom))?;
+                }
+            }
+        }
+
+        self.results.push(ActionResult::Failed {
+            code,
+            error,
+        });
+
+        let response = response.add_attribute("action_failed", action.ty());
+
+        self.handle_next_action(deps, env, Some(response))
+    }
+
+    fn handle_transfer(
+        &mut self,
+        response:  Response<CustomMsg>,
+        deps:      DepsMut,
+        env:       Env,
+        src_denom: String,
+        amount:    Uint128,
+        recipient: Option<String>,
+    ) -> Result<Response<CustomMsg>> {
         let recipient = match recipient {
             // if the sender doesn't specify the recipient, default to
             // their interchain account
@@ -162,97 +378,236 @@ impl Handler {
             Some(r) => deps.api.addr_validate(&r)?,
         };
 
+        // if `Execute` actions are to send some of the tokens to the ICA, they
+        // are received by the contract itself, which holds onto them until then
+        let hold = self.host.as_ref() == Some(&recipient) && !self.reserved.is_empty();
+        let receiver = if hold {
+            env.contract.address.as_str()
+        } else {
+            recipient.as_str()
+        };
+
+        let received = self.receive_tokens(deps, &env, src_denom, amount, receiver)?;
+
+        let mut msgs = received.msgs;
+
+        if hold {
+            let held = self.reserved.amount_of(&received.denom).min(amount);
+            let held_coin = Coin::new(held.u128(), &received.denom);
+            self.reserved.deduct(held_coin.clone())?;
+            self.held.add(held_coin)?;
+
+            // deliver the rest to the ICA right away
+            let rest = amount - held;
+            if !rest.is_zero() {
+                msgs.push(send_msg(Coin::new(rest.u128(), &received.denom), &recipient)?);
+            }
+        }
+
+        self.results.push(ActionResult::Transfer {
+            denom:              received.denom,
+            new_token:          received.new_token,
+            recipient:          recipient.into(),
+            denom_creation_fee: received.denom_creation_fee,
+        });
+
+        // the last message is the one delivering the tokens
+        let deliver_msg = msgs.pop().expect("receiving always produces at least one message");
+
+        Ok(response
+            .add_attribute("action", "transfer")
+            .add_messages(msgs)
+            .add_submessage(SubMsg::reply_on_success(deliver_msg, AFTER_ACTION)))
+    }
+
+    /// Receive tokens sent along with the packet, and deliver them to the
+    /// recipient: if the sender chain is the source, mint vouchers; otherwise,
+    /// release them from escrow.
+    fn receive_tokens(
+        &self,
+        deps:      DepsMut,
+        env:       &Env,
+        src_denom: String,
+        amount:    Uint128,
+        recipient: &str,
+    ) -> Result<ReceivedTokens> {
+        let mut trace: TraceItem = self
+            .traces
+            .iter()
+            .find(|trace| trace.denom == src_denom)
+            .ok_or(Error::TraceNotFound {
+                denom: src_denom,
+            })?
+            .into();
+
+        let connection_id = CHANNEL_CONNECTIONS.load(deps.storage, &self.endpoint.channel_id)?;
+
         if trace.sender_is_source(&self.counterparty_endpoint) {
             // append current chain to the path
             trace.path.push(self.endpoint.clone());
 
             // derive the ibc denom
-            let subdenom = trace.hash().to_hex();
-            let denom = construct_denom(env.contract.address.as_str(), &subdenom);
+            let denom = voucher_denom(deps.as_ref(), env.contract.address.as_str(), &trace)?;
 
             let new_token = !DENOM_TRACES.has(deps.storage, &denom);
 
+            record_inflow(deps.storage, &deps.querier, &env.block, &connection_id, &denom, amount)?;
+
+            let backend = CONFIG.load(deps.storage)?.voucher_backend;
+
+            let mut msgs = vec![];
+            let mut denom_creation_fee = vec![];
+
             // if the denom does not exist yet -- create the denom and
             // save the trace to store
             if new_token {
                 DENOM_TRACES.save(deps.storage, &denom, &trace)?;
 
-                // we can only create the denom if denom creation fee
-                // is zero
-                assert_free_denom_creation(&deps.querier)?;
+                match &backend {
+                    VoucherBackend::TokenFactory {
+                        flavor,
+                    } => {
+                        let tokenfactory = flavor.backend();
 
-                response = response.add_message(tokenfactory::MsgCreateDenom {
-                    sender: env.contract.address.to_string(),
-                    subdenom,
-                });
-            }
+                        // the denom creation fee, if any, is paid from the pool
+                        denom_creation_fee = tokenfactory.denom_creation_fee(&deps.querier)?;
+                        pay_denom_creation_fee(deps.storage, &denom_creation_fee)?;
 
-            self.results.push(ActionResult::Transfer {
-                denom:     denom.clone(),
-                new_token,
-                recipient: recipient.to_string(),
-            });
+                        msgs.push(tokenfactory.create_denom(
+                            env.contract.address.as_str(),
+                            &trace.hash().to_hex(),
+                        )?);
+                    },
+                    VoucherBackend::Cw20 {
+                        code_id,
+                    } => {
+                        msgs.push(
+                            cw20_voucher_instantiate_msg(
+                                *code_id,
+                                env.contract.address.as_str(),
+                                &trace,
+                            )?
+                            .into(),
+                        );
+                    },
+                }
+            }
 
             let coin = Coin {
-                denom,
+                denom: denom.clone(),
                 amount,
             };
 
-            // tokenfactory only supports minting to the sender
-            // therefore we first mint to ourself, then transfer to the recipient
-            Ok(response
-                .add_message(tokenfactory::MsgMint {
-                    sender:          env.contract.address.clone().into(),
-                    mint_to_address: env.contract.address.into(),
-                    amount:          Some(into_proto_coin(coin.clone())),
-                })
-                .add_submessage(SubMsg::reply_on_success(
-                    BankMsg::Send {
-                        to_address: recipient.into(),
-                        amount:     vec![coin],
-                    },
-                    AFTER_ACTION,
-                )))
+            add_voucher_supply(deps.storage, &coin)?;
+
+            // depending on the backend, the tokens are either minted to the
+            // recipient directly, or minted to ourself then transferred
+            let sender = env.contract.address.as_str();
+            msgs.extend(mint_msgs(&backend, sender, recipient, coin)?);
+
+            Ok(ReceivedTokens {
+                denom,
+                new_token,
+                denom_creation_fee,
+                msgs,
+            })
         } else {
             // pop the sender chain from the path
             trace.path.pop();
 
-            // derive the ibc denom
-            let denom = if trace.path.is_empty() {
-                trace.base_denom
-            } else {
-                let subdenom = trace.hash().to_hex();
-                construct_denom(env.contract.address.as_str(), &subdenom)
+            // derive the denom on this chain:
+            // - if the path is empty, the token is native to this chain
+            // - if the last step is not an ICS-999 channel, the token arrived
+            //   on this chain through ICS-20
+            // - otherwise, it is a voucher created by us
+            let denom = match trace.path.last() {
+                None => trace.base_denom.clone(),
+                Some(step) if step.port_id != self.endpoint.port_id => trace.ics20_denom(),
+                Some(_) => voucher_denom(deps.as_ref(), env.contract.address.as_str(), &trace)?,
             };
 
-            self.results.push(ActionResult::Transfer {
-                denom:     denom.clone(),
-                new_token: false,
-                recipient: recipient.to_string(),
-            });
+            record_inflow(deps.storage, &deps.querier, &env.block, &connection_id, &denom, amount)?;
 
             let coin = Coin {
-                denom,
+                denom: denom.clone(),
                 amount,
             };
 
-            Ok(response.add_submessage(SubMsg::reply_on_success(
-                BankMsg::Send {
-                    to_address: recipient.into(),
-                    amount:     vec![coin],
-                },
-                AFTER_ACTION,
-            )))
+            // the tokens must have been escrowed when sent through the channel
+            deduct_escrow(deps.storage, &self.endpoint.channel_id, &coin)?;
+
+            Ok(ReceivedTokens {
+                denom,
+                new_token: false,
+                denom_creation_fee: vec![],
+                msgs: vec![send_msg(coin, recipient)?],
+            })
         }
     }
 
+    /// Receive the tokens to be forwarded, then send the actions onward,
+    /// transferring the tokens under their denoms on this chain.
+    fn handle_forward(
+        &mut self,
+        mut response:  Response<CustomMsg>,
+        mut deps:      DepsMut,
+        env:           &Env,
+        connection_id: String,
+        mut actions:   Vec<Action>,
+    ) -> Result<Response<CustomMsg>> {
+        for (denom, amount) in transfers_mut(&mut actions) {
+            let received = self.receive_tokens(
+                deps.branch(),
+                env,
+                denom.clone(),
+                amount,
+                env.contract.address.as_str(),
+            )?;
+
+            *denom = received.denom;
+            response = response.add_messages(received.msgs);
+        }
+
+        let SentPacket {
+            msgs,
+            send_msg,
+            attrs,
+            mut records,
+            ..
+        } = send_packet(
+            deps.branch(),
+            env,
+            &connection_id,
+            forwarded_controller(&self.endpoint, &self.controller),
+            actions,
+            None,
+            false,
+        )?;
+
+        records.origin = Some(ForwardOrigin {
+            endpoint:   self.endpoint.clone(),
+            sequence:   self.sequence,
+            controller: self.controller.clone(),
+        });
+
+        // the forwarded packet's sequence is only known once it's sent, so its
+        // records are saved, and its result recorded, in the reply
+        SENDING_PACKET.save(deps.storage, &records)?;
+
+        Ok(response
+            .add_attribute("action", "forward")
+            .add_attributes(attrs)
+            .add_messages(msgs)
+            .add_submessage(SubMsg::reply_on_success(send_msg, AFTER_ACTION)))
+    }
+
     fn handle_register_account_default(
         &mut self,
-        response: Response,
+        response: Response<CustomMsg>,
         deps:     DepsMut,
         env:      Env,
         salt:     Option<Binary>,
-    ) -> Result<Response> {
+    ) -> Result<Response<CustomMsg>> {
         // only one ICA per controller allowed
         self.assert_no_host()?;
 
@@ -299,10 +654,10 @@ impl Handler {
 
     fn handle_register_account_custom_factory(
         &self,
-        response: Response,
+        response: Response<CustomMsg>,
         factory:  String,
         data:     Option<Binary>,
-    ) -> Result<Response> {
+    ) -> Result<Response<CustomMsg>> {
         // only one ICA per controller allowed
         self.assert_no_host()?;
 
@@ -324,31 +679,92 @@ impl Handler {
 
     fn handle_query(
         &mut self,
-        response: Response,
+        response: Response<CustomMsg>,
         deps:     Deps,
         msg:      Binary,
-    ) -> Result<Response> {
+    ) -> Result<Response<CustomMsg>> {
         let addr = self.get_host()?;
 
-        let query_req = to_binary(&QueryRequest::<Empty>::Wasm(WasmQuery::Smart {
+        let query_res = raw_query(deps, &QueryRequest::Wasm(WasmQuery::Smart {
             contract_addr: addr.into(),
             msg,
         }))?;
 
-        let query_res = deps
-            .querier
-            .raw_query(&query_req)
-            .into_result()?
-            .into_result()
-            .map_err(Error::QueryContract)?;
-
         self.results.push(ActionResult::Query { response: query_res });
 
         Ok(response.add_attribute("action", "query"))
     }
 
-    fn handle_execute(&self, response: Response, msg: Binary) -> Result<Response> {
-        let addr = self.get_host()?;
+    fn handle_query_chain(
+        &mut self,
+        response: Response<CustomMsg>,
+        deps:     Deps,
+        request:  QueryRequest<Empty>,
+    ) -> Result<Response<CustomMsg>> {
+        let query_res = raw_query(deps, &request)?;
+
+        self.results.push(ActionResult::Query { response: query_res });
+
+        Ok(response.add_attribute("action", "query_chain"))
+    }
+
+    fn handle_assert(
+        &mut self,
+        response:  Response<CustomMsg>,
+        deps:      Deps,
+        assertion: Assertion,
+    ) -> Result<Response<CustomMsg>> {
+        match assertion {
+            Assertion::Balance {
+                address,
+                denom,
+                op,
+                amount,
+            } => {
+                let address = match address {
+                    None => self.get_host()?.to_string(),
+                    Some(address) => address,
+                };
+
+                assert_balance(deps, &address, &denom, &op, amount)?;
+            },
+            Assertion::Query {
+                request,
+                path,
+                op,
+                value,
+            } => {
+                let query_res = raw_query(deps, &request)?;
+
+                assert_query_response(&query_res, &path, &op, &value)?;
+            },
+        }
+
+        self.results.push(ActionResult::Assert {});
+
+        Ok(response.add_attribute("action", "assert"))
+    }
+
+    fn handle_execute(
+        &mut self,
+        response: Response<CustomMsg>,
+        msg:      Binary,
+        funds:    Vec<Coin>,
+    ) -> Result<Response<CustomMsg>> {
+        let addr = self.get_host()?.clone();
+
+        // the funds must have been delivered by earlier transfers
+        for coin in &funds {
+            let delivered = self.held.amount_of(&coin.denom);
+            if delivered < coin.amount {
+                return Err(Error::ExecuteFundsNotDelivered {
+                    required:  coin.clone(),
+                    delivered: Coin::new(delivered.u128(), &coin.denom),
+                });
+            }
+
+            self.held.deduct(coin.clone())?;
+        }
 
         Ok(response
             .add_attribute("action", "execute")
@@ -356,7 +772,7 @@ impl Handler {
                 WasmMsg::Execute {
                     contract_addr: addr.into(),
                     msg,
-                    funds: vec![],
+                    funds,
                 },
                 AFTER_ACTION,
             )))
@@ -381,21 +797,37 @@ impl Handler {
     }
 
     /// After an `Execute` action has been completed, parse the response
-    pub fn after_action(&mut self, deps: DepsMut, data: Option<Binary>) -> Result<()> {
+    pub fn after_action(&mut self, deps: DepsMut, res: SubMsgResponse) -> Result<()> {
         // the action that was executed
         let action = self.action.as_ref().expect("missing active action");
 
-        if let Action::Execute(_) = action {
-            return self.after_execute(data);
+        if let Action::Execute { .. } = action {
+            return self.after_execute(res.data);
         }
 
         if let Action::RegisterAccount(RegisterOptions::CustomFactory { .. }) = action {
-            return self.after_register_account_custom_factory(deps, data);
+            return self.after_register_account_custom_factory(deps, res.data);
+        }
+
+        if let Action::Forward { .. } = action {
+            return self.after_forward(deps, &res.events);
         }
 
         Ok(())
     }
 
+    fn after_forward(&mut self, deps: DepsMut, events: &[Event]) -> Result<()> {
+        let (channel_id, sequence) = save_sent_packet(deps.storage, events)?;
+
+        self.results.push(ActionResult::Forward {
+            channel_id,
+            sequence,
+            outcome: None,
+        });
+
+        Ok(())
+    }
+
     fn after_execute(&mut self, data: Option<Binary>) -> Result<()> {
         // note that the contract being executed does not necessarily return
         // any data
@@ -454,3 +886,476 @@ impl Handler {
         hasher.finalize().to_vec().into()
     }
 }
+
+/// Perform a query, returning the response in raw binary format
+fn raw_query(deps: Deps, request: &QueryRequest<Empty>) -> Result<Binary> {
+    deps.querier
+        .raw_query(&to_binary(request)?)
+        .into_result()?
+        .into_result()
+        .map_err(Error::QueryContract)
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coin, from_binary,
+        testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage},
+        BalanceResponse, BankMsg, BankQuery, IbcMsg, OwnedDeps, ReplyOn, SubMsgResult,
+    };
+    use ics999::Comparison;
+
+    use super::*;
+    use crate::{
+        host::{after_action, after_isolated_action, handle_action},
+        msg::{Config, TokenFactoryFlavor},
+        state::{ACTIVE_CHANNELS, ESCROWS, FORWARDS, PACKETS},
+    };
+
+    fn endpoint(port_id: &str, channel_id: &str) -> IbcEndpoint {
+        IbcEndpoint {
+            port_id:    port_id.into(),
+            channel_id: channel_id.into(),
+        }
+    }
+
+    fn transfer(amount: u128) -> Action {
+        Action::Transfer {
+            denom:     "factory/counterparty/uosmo".into(),
+            amount:    Uint128::new(amount),
+            recipient: None,
+        }
+    }
+
+    fn execute(funds: Vec<Coin>) -> Action {
+        Action::Execute {
+            msg: b"{}".into(),
+            funds,
+        }
+    }
+
+    fn mock_deps() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies();
+        deps.querier.update_balance("larry", vec![coin(12345, "uosmo")]);
+
+        CHANNEL_CONNECTIONS
+            .save(deps.as_mut().storage, "channel-0", &"connection-0".into())
+            .unwrap();
+        ESCROWS
+            .save(deps.as_mut().storage, ("channel-0", "uosmo"), &Uint128::new(12345))
+            .unwrap();
+        ACCOUNTS
+            .save(
+                deps.as_mut().storage,
+                ("wasm.core", "channel-0", "larry"),
+                &Addr::unchecked("ica"),
+            )
+            .unwrap();
+
+        deps
+    }
+
+    /// Create a handler for a packet. Only larry owns an ICA.
+    fn create(
+        deps:          Deps,
+        controller:    &str,
+        actions:       Vec<Action>,
+        allow_failure: bool,
+    ) -> Result<Handler> {
+        let src = endpoint("wasm.counterparty", "channel-9");
+
+        // a token native to this chain, which is released from escrow
+        let traces = vec![Trace {
+            denom:      "factory/counterparty/uosmo".into(),
+            base_denom: "uosmo".into(),
+            path:       vec![src.clone()],
+        }];
+
+        Handler::create(
+            deps.storage,
+            src,
+            endpoint("wasm.core", "channel-0"),
+            1,
+            controller.into(),
+            actions,
+            traces,
+            allow_failure,
+        )
+    }
+
+    fn ok(data: Option<Binary>) -> SubMsgResult {
+        SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data,
+        })
+    }
+
+    /// Handle all actions in an atomic packet, each in a submessage of its own,
+    /// skipping the replies to the messages they emit, which don't matter for
+    /// transfers and executes. Returns the response of each action, and the
+    /// ack of the packet.
+    fn handle_all(
+        controller: &str,
+        actions:    Vec<Action>,
+    ) -> Result<(Vec<Response<CustomMsg>>, PacketAck)> {
+        let mut deps = mock_deps();
+        let handler = create(deps.as_ref(), controller, actions, false)?;
+
+        let mut responses = vec![];
+        let mut res = handler.handle_next_action(deps.as_mut(), mock_env(), None)?;
+        while HANDLER.exists(deps.as_ref().storage) {
+            let action_res = handle_action(deps.as_mut(), mock_env())?;
+            let data = action_res.data.clone().map(execute_data);
+            responses.push(action_res);
+
+            res = after_isolated_action(deps.as_mut(), mock_env(), ok(data))?;
+        }
+
+        let ack = from_binary(&res.data.unwrap())?;
+        Ok((responses, ack))
+    }
+
+    fn bank_send(to: &str, amount: u128) -> CosmosMsg<CustomMsg> {
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: to.into(),
+            amount:     vec![coin(amount, "uosmo")],
+        })
+    }
+
+    #[test]
+    fn executing_with_funds() {
+        // without execute funds, the tokens are delivered to the ICA directly
+        {
+            let (responses, _) = handle_all("larry", vec![transfer(100), execute(vec![])]).unwrap();
+            assert_eq!(responses[0].messages.len(), 1);
+            assert_eq!(responses[0].messages[0].msg, bank_send("ica", 100));
+        }
+
+        // the funds are held by the contract, the rest is delivered right away
+        {
+            let (responses, _) =
+                handle_all("larry", vec![transfer(100), execute(vec![coin(60, "uosmo")])]).unwrap();
+            assert_eq!(responses[0].messages.len(), 2);
+            assert_eq!(responses[0].messages[0].msg, bank_send("cosmos2contract", 100));
+            assert_eq!(responses[0].messages[1].msg, bank_send("ica", 40));
+            assert_eq!(
+                responses[1].messages[0].msg,
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "ica".into(),
+                    msg:           b"{}".into(),
+                    funds:         vec![coin(60, "uosmo")],
+                }),
+            );
+        }
+
+        // the funds exceed the amount delivered. as the transfer took effect,
+        // the packet fails entirely
+        {
+            let err = handle_all("larry", vec![transfer(100), execute(vec![coin(150, "uosmo")])])
+                .unwrap_err();
+            assert_eq!(err, Error::ActionFailed {
+                index: 1,
+                code:  ErrorCode::InsufficientFunds,
+                error: Error::ExecuteFundsNotDelivered {
+                    required:  coin(150, "uosmo"),
+                    delivered: coin(100, "uosmo"),
+                }
+                .to_string(),
+            });
+        }
+
+        // cw20 tokens can't be sent along with an execute
+        {
+            let actions = vec![transfer(100), execute(vec![coin(60, "cw20:token")])];
+            let err = handle_all("larry", actions).unwrap_err();
+            assert_eq!(err, Error::Cw20ExecuteFunds {
+                denom: "cw20:token".into(),
+            });
+        }
+
+        // the funds must be delivered before the execute. as nothing else took
+        // effect, the ack says which action failed and why, and the transfer
+        // isn't executed
+        {
+            let (responses, ack) =
+                handle_all("larry", vec![execute(vec![coin(60, "uosmo")]), transfer(100)])
+                    .unwrap();
+            assert_eq!(responses.len(), 1);
+            assert_eq!(ack, PacketAck::Error {
+                index: Some(0),
+                code:  ErrorCode::InsufficientFunds,
+                error: Error::ExecuteFundsNotDelivered {
+                    required:  coin(60, "uosmo"),
+                    delivered: coin(0, "uosmo"),
+                }
+                .to_string(),
+            });
+        }
+    }
+
+    #[test]
+    fn releasing_from_escrow() {
+        // no more can be released than was escrowed for the channel
+        {
+            let (_, ack) = handle_all("larry", vec![transfer(12346)]).unwrap();
+            assert_eq!(ack, PacketAck::Error {
+                index: Some(0),
+                code:  ErrorCode::InsufficientFunds,
+                error: Error::InsufficientEscrow {
+                    channel_id: "channel-0".into(),
+                    escrowed:   coin(12345, "uosmo"),
+                    amount:     coin(12346, "uosmo"),
+                }
+                .to_string(),
+            });
+        }
+
+        // releasing the full amount empties the escrow
+        let mut deps = mock_deps();
+        let handler = create(deps.as_ref(), "larry", vec![transfer(12345)], false).unwrap();
+        handler.handle_next_action(deps.as_mut(), mock_env(), None).unwrap();
+        handle_action(deps.as_mut(), mock_env()).unwrap();
+        assert!(!ESCROWS.has(deps.as_ref().storage, ("channel-0", "uosmo")));
+    }
+
+    #[test]
+    fn querying_chain() {
+        // no ICA is needed
+        let (_, ack) = handle_all("jake", vec![Action::QueryChain(QueryRequest::Bank(
+            BankQuery::Balance {
+                address: "larry".into(),
+                denom:   "uosmo".into(),
+            },
+        ))])
+        .unwrap();
+        assert_eq!(ack, PacketAck::Success(vec![ActionResult::Query {
+            response: to_binary(&BalanceResponse {
+                amount: coin(12345, "uosmo"),
+            })
+            .unwrap(),
+        }]));
+
+        // errors are propagated
+        let (_, ack) =
+            handle_all("jake", vec![Action::QueryChain(QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: "counter".into(),
+                msg:           b"{}".into(),
+            }))])
+            .unwrap();
+        assert!(matches!(ack, PacketAck::Error {
+            index: Some(0),
+            code:  ErrorCode::QueryFailed,
+            ..
+        }));
+    }
+
+    #[test]
+    fn asserting() {
+        let assert_balance = |address: Option<&str>, amount: u128| {
+            Action::Assert(Assertion::Balance {
+                address: address.map(Into::into),
+                denom:   "uosmo".into(),
+                op:      Comparison::Gte,
+                amount:  Uint128::new(amount),
+            })
+        };
+
+        // the assertion holds, so the packet goes on
+        let (_, ack) =
+            handle_all("larry", vec![assert_balance(Some("larry"), 12345), transfer(100)]).unwrap();
+        let PacketAck::Success(results) = ack else {
+            panic!("expecting a success ack, found: {ack:?}");
+        };
+        assert_eq!(results[0], ActionResult::Assert {});
+
+        // the address defaults to the sender's ICA, which holds nothing. as the
+        // transfer took effect, the packet fails entirely
+        let err = handle_all("larry", vec![transfer(100), assert_balance(None, 1)]).unwrap_err();
+        assert!(matches!(err, Error::ActionFailed {
+            index: 1,
+            code:  ErrorCode::AssertionFailed,
+            ..
+        }));
+    }
+
+    #[test]
+    fn allowing_failure() {
+        let mut deps = mock_deps();
+
+        let actions = vec![
+            transfer(100),
+            execute(vec![coin(60, "uosmo")]),
+            execute(vec![coin(150, "uosmo")]),
+        ];
+        let handler = create(deps.as_ref(), "larry", actions, true).unwrap();
+
+        // each action is executed in a submessage of its own
+        let res = handler.handle_next_action(deps.as_mut(), mock_env(), None).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].id, AFTER_ISOLATED_ACTION);
+        assert_eq!(res.messages[0].reply_on, ReplyOn::Always);
+
+        // the transfer succeeds, and the tokens for the executes are held
+        let res = handle_action(deps.as_mut(), mock_env()).unwrap();
+        assert_eq!(res.messages[0].msg, bank_send("cosmos2contract", 100));
+
+        // the submessage executing it returns before moving on
+        let res = after_action(deps.as_mut(), ok(None)).unwrap();
+        assert!(res.messages.is_empty());
+
+        let res = after_isolated_action(deps.as_mut(), mock_env(), ok(None)).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].id, AFTER_ISOLATED_ACTION);
+
+        // the first execute fails in the ICA, so the error is redacted. the
+        // tokens held for it are delivered to the ICA
+        let error = "codespace: wasm, code: 5".to_string();
+        let res = after_isolated_action(deps.as_mut(), mock_env(), SubMsgResult::Err(error.clone()))
+            .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.messages[0].msg, bank_send("ica", 60));
+
+        // the second execute fails due to our own error, which is returned as
+        // data in full, and none of the its state changes are written
+        let res = handle_action(deps.as_mut(), mock_env()).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(Handler::load(deps.as_ref().storage).unwrap().held, Coins::from(vec![coin(
+            40, "uosmo",
+        )]));
+
+        let res = after_isolated_action(deps.as_mut(), mock_env(), ok(res.data.map(execute_data)))
+            .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, bank_send("ica", 40));
+
+        let PacketAck::Success(results) = from_binary(&res.data.unwrap()).unwrap() else {
+            panic!("expecting a success ack");
+        };
+        assert_eq!(results[1], ActionResult::Failed {
+            code: ErrorCode::ContractError,
+            error,
+        });
+        assert_eq!(results[2], ActionResult::Failed {
+            code:  ErrorCode::InsufficientFunds,
+            error: Error::ExecuteFundsNotDelivered {
+                required:  coin(150, "uosmo"),
+                delivered: coin(40, "uosmo"),
+            }
+            .to_string(),
+        });
+        assert!(!HANDLER.exists(deps.as_ref().storage));
+    }
+
+    #[test]
+    fn allowing_failure_before_funds_are_delivered() {
+        let mut deps = mock_deps();
+
+        let actions = vec![execute(vec![coin(60, "uosmo")]), transfer(100)];
+        let handler = create(deps.as_ref(), "larry", actions, true).unwrap();
+        handler.handle_next_action(deps.as_mut(), mock_env(), None).unwrap();
+
+        // the execute fails as its funds haven't been delivered yet, so they no
+        // longer need to be held
+        let res = handle_action(deps.as_mut(), mock_env()).unwrap();
+        after_isolated_action(deps.as_mut(), mock_env(), ok(res.data.map(execute_data))).unwrap();
+        assert!(Handler::load(deps.as_ref().storage).unwrap().reserved.is_empty());
+
+        // the transfer delivers all tokens to the ICA right away
+        let res = handle_action(deps.as_mut(), mock_env()).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, bank_send("ica", 100));
+
+        let res = after_isolated_action(deps.as_mut(), mock_env(), ok(None)).unwrap();
+        assert!(res.messages.is_empty());
+        assert!(!HANDLER.exists(deps.as_ref().storage));
+    }
+
+    #[test]
+    fn delivering_leftover_held_funds() {
+        let mut deps = mock_deps();
+
+        let actions = vec![transfer(100)];
+        let mut handler = create(deps.as_ref(), "larry", actions, true).unwrap();
+        handler.held = Coins::from(vec![coin(60, "uosmo")]);
+        handler.handle_next_action(deps.as_mut(), mock_env(), None).unwrap();
+
+        handle_action(deps.as_mut(), mock_env()).unwrap();
+
+        // coins that are still held once the queue is empty go to the ICA
+        let res = after_isolated_action(deps.as_mut(), mock_env(), ok(None)).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, bank_send("ica", 60));
+    }
+
+    #[test]
+    fn forwarding() {
+        let mut deps = mock_deps();
+
+        CONFIG
+            .save(deps.as_mut().storage, &Config {
+                default_account_code_id:    1,
+                default_timeout_secs:       300,
+                max_timeout_secs:           86_400,
+                voucher_backend:            VoucherBackend::TokenFactory {
+                    flavor: TokenFactoryFlavor::Osmosis,
+                },
+                default_callback_gas_limit: 1_000_000,
+                max_callback_gas_limit:     5_000_000,
+            })
+            .unwrap();
+        ACTIVE_CHANNELS
+            .save(deps.as_mut().storage, "connection-1", &endpoint("wasm.core", "channel-1"))
+            .unwrap();
+
+        let forward = Action::Forward {
+            connection_id: "connection-1".into(),
+            actions:       vec![Action::RegisterAccount(RegisterOptions::Default {
+                salt: None,
+            })],
+        };
+        let handler = create(deps.as_ref(), "larry", vec![forward], false).unwrap();
+
+        // the packet is sent in a submessage, whose reply carries its sequence
+        handler.handle_next_action(deps.as_mut(), mock_env(), None).unwrap();
+        let res = handle_action(deps.as_mut(), mock_env()).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].id, AFTER_ACTION);
+        assert!(matches!(res.messages[0].msg, CosmosMsg::Ibc(IbcMsg::SendPacket { .. })));
+
+        let reply = SubMsgResult::Ok(SubMsgResponse {
+            events: vec![Event::new("send_packet").add_attribute("packet_sequence", "42")],
+            data:   None,
+        });
+        after_action(deps.as_mut(), reply).unwrap();
+
+        let res = after_isolated_action(deps.as_mut(), mock_env(), ok(None)).unwrap();
+        let ack: PacketAck = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(ack, PacketAck::Success(vec![ActionResult::Forward {
+            channel_id: "channel-1".into(),
+            sequence:   42,
+            outcome:    None,
+        }]));
+
+        let origin = FORWARDS.load(deps.as_ref().storage, ("channel-1", 42)).unwrap();
+        assert_eq!(origin.endpoint, endpoint("wasm.core", "channel-0"));
+        assert_eq!(origin.sequence, 1);
+        assert!(PACKETS.has(deps.as_ref().storage, ("channel-1", 42)));
+        assert!(!SENDING_PACKET.exists(deps.as_ref().storage));
+    }
+
+    /// Wrap the data returned by a contract the way wasmd does, as the data of
+    /// a `MsgExecuteContractResponse`
+    fn execute_data(data: Binary) -> Binary {
+        let mut bytes = vec![0x0a];
+        let mut len = data.len();
+        while len >= 0x80 {
+            bytes.push((len as u8 & 0x7f) | 0x80);
+            len >>= 7;
+        }
+        bytes.push(len as u8);
+        bytes.extend(data.as_slice());
+        bytes.into()
+    }
+}
@@ -1,36 +1,70 @@
 mod handler;
+mod placeholder;
 
 use {
     self::handler::Handler,
-    crate::{error::Result, msg::ExecuteMsg, AFTER_ALL_ACTIONS},
+    crate::{
+        error::{redacted_error_code, Result},
+        forward::receive_forward_ack,
+        msg::ExecuteMsg,
+        pause::assert_not_paused,
+        state::CHANNEL_CONNECTIONS,
+        utils::StorageBuffer,
+        CustomMsg,
+        AFTER_ALL_ACTIONS,
+    },
     cosmwasm_std::{
-        from_slice, to_binary, DepsMut, Env, IbcEndpoint, IbcPacket, IbcReceiveResponse, Response,
-        SubMsg, SubMsgResponse, SubMsgResult, WasmMsg,
+        from_binary, from_slice, to_binary, DepsMut, Env, IbcEndpoint, IbcPacket,
+        IbcReceiveResponse, Response, SubMsg, SubMsgResponse, SubMsgResult, WasmMsg,
     },
     cw_utils::parse_execute_response_data,
-    ics999::{Action, PacketAck, PacketData, Trace},
+    ics999::{Action, ActionResult, PacketAck, PacketData, Trace},
 };
 
-pub fn packet_receive(env: Env, packet: IbcPacket) -> Result<IbcReceiveResponse> {
+pub fn packet_receive(
+    deps:   DepsMut,
+    env:    Env,
+    packet: IbcPacket,
+) -> Result<IbcReceiveResponse<CustomMsg>> {
     // deserialize packet data
     let pd: PacketData = from_slice(&packet.data)?;
 
-    // we don't add an ack in this response
-    // the ack will be added in after_all_actions reply (see below)
-    Ok(IbcReceiveResponse::new()
+    // the packet relays the outcome of a packet forwarded on behalf of a packet
+    // sent by this chain
+    if let Some(forward_ack) = pd.forward_ack {
+        return receive_forward_ack(deps, env, packet, forward_ack);
+    }
+
+    let res = IbcReceiveResponse::new()
         .add_attribute("method", "packet_receive")
         .add_attribute("port_id", &packet.dest.port_id)
         .add_attribute("channel_id", &packet.dest.channel_id)
-        .add_attribute("sequence", packet.sequence.to_string())
-        .add_submessage(SubMsg::reply_always(
+        .add_attribute("sequence", packet.sequence.to_string());
+
+    // if the protocol, the connection, or any of the actions is paused, reject
+    // the packet with an error ack, so that the sender gets refunded
+    let connection_id = CHANNEL_CONNECTIONS.load(deps.storage, &packet.dest.channel_id)?;
+    if let Err(err) = assert_not_paused(deps.storage, &connection_id, &pd.actions) {
+        return Ok(res.add_attribute("paused", "true").set_ack(to_binary(&PacketAck::Error {
+            index: None,
+            code:  err.code(),
+            error: err.to_string(),
+        })?));
+    }
+
+    // we don't add an ack in this response
+    // the ack will be added in after_all_actions reply (see below)
+    Ok(res.add_submessage(SubMsg::reply_always(
             WasmMsg::Execute {
                 contract_addr: env.contract.address.into(),
                 msg: to_binary(&ExecuteMsg::Handle {
                     counterparty_endpoint: packet.src,
                     endpoint:              packet.dest,
+                    sequence:              packet.sequence,
                     controller:            pd.controller,
                     actions:               pd.actions,
                     traces:                pd.traces,
+                    allow_failure:         pd.allow_failure,
                 })?,
                 funds: vec![],
             },
@@ -38,28 +72,107 @@ pub fn packet_receive(env: Env, packet: IbcPacket) -> Result<IbcReceiveResponse>
         )))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle(
-    deps:       DepsMut,
-    env:        Env,
-    src:        IbcEndpoint,
-    dest:       IbcEndpoint,
-    controller: String,
-    actions:    Vec<Action>,
-    traces:     Vec<Trace>,
-) -> Result<Response> {
-    let handler = Handler::create(deps.storage, src, dest, controller, actions, traces)?;
+    deps:          DepsMut,
+    env:           Env,
+    src:           IbcEndpoint,
+    dest:          IbcEndpoint,
+    sequence:      u64,
+    controller:    String,
+    actions:       Vec<Action>,
+    traces:        Vec<Trace>,
+    allow_failure: bool,
+) -> Result<Response<CustomMsg>> {
+    let handler = Handler::create(
+        deps.storage,
+        src,
+        dest,
+        sequence,
+        controller,
+        actions,
+        traces,
+        allow_failure,
+    )?;
     handler.handle_next_action(deps, env, None)
 }
 
-pub fn after_action(mut deps: DepsMut, env: Env, res: SubMsgResult) -> Result<Response> {
+pub fn handle_action(deps: DepsMut, env: Env) -> Result<Response<CustomMsg>> {
+    let handler = Handler::load(deps.storage)?;
+    let response = Response::new().add_attribute("method", "handle_action");
+
+    // if the action fails, we return the error as data instead, so that it
+    // isn't redacted. its state changes are buffered, so they can be discarded
+    let mut buffer = StorageBuffer::new(deps.storage);
+    let buffered_deps = DepsMut {
+        storage: &mut buffer,
+        api:     deps.api,
+        querier: deps.querier,
+    };
+
+    match handler.handle_action(buffered_deps, env, response) {
+        Ok(response) => {
+            buffer.commit();
+            Ok(response)
+        },
+        Err(err) => Ok(Response::new()
+            .add_attribute("method", "handle_action")
+            .add_attribute("error", err.to_string())
+            .set_data(to_binary(&ActionResult::Failed {
+                code:  err.code(),
+                error: err.to_string(),
+            })?)),
+    }
+}
+
+pub fn after_action(mut deps: DepsMut, res: SubMsgResult) -> Result<Response<CustomMsg>> {
     let mut handler = Handler::load(deps.storage)?;
-    handler.after_action(deps.branch(), res.unwrap().data)?; // reply on success so unwrap can't fail
-    handler.handle_next_action(deps, env, None)
+    handler.after_action(deps.branch(), res.unwrap())?; // reply on success so unwrap can't fail
+    handler.finish_action(deps, None)
 }
 
-pub fn after_all_actions(res: SubMsgResult) -> Result<Response> {
+pub fn after_isolated_action(
+    deps: DepsMut,
+    env:  Env,
+    res:  SubMsgResult,
+) -> Result<Response<CustomMsg>> {
+    let handler = Handler::load(deps.storage)?;
+
+    let failure = match res {
+        // if the action failed with an error raised by ourselves, it is
+        // returned as data. otherwise it succeeded, and its result has been
+        // recorded already
+        SubMsgResult::Ok(SubMsgResponse {
+            data,
+            ..
+        }) => data
+            .map(|bin| parse_execute_response_data(&bin))
+            .transpose()?
+            .and_then(|res| res.data)
+            .map(|bin| from_binary(&bin))
+            .transpose()?,
+
+        // the action failed due to an error raised by another module or
+        // contract, which is redacted
+        SubMsgResult::Err(err) => Some(ActionResult::Failed {
+            code:  redacted_error_code(&err),
+            error: err,
+        }),
+    };
+
+    match failure {
+        Some(ActionResult::Failed {
+            code,
+            error,
+        }) => handler.after_failed_action(deps, env, code, error),
+        _ => handler.after_succeeded_action(deps, env),
+    }
+}
+
+pub fn after_all_actions(res: SubMsgResult) -> Result<Response<CustomMsg>> {
     let ack = match &res {
-        // all actions were successful - write an Success ack
+        // all actions were successful, or one of them failed without any
+        // having taken effect - write the ack returned
         SubMsgResult::Ok(SubMsgResponse {
             data,
             ..
@@ -67,19 +180,21 @@ pub fn after_all_actions(res: SubMsgResult) -> Result<Response> {
             let execute_res_bin = data.as_ref().expect("missing execute response data");
             let execute_res = parse_execute_response_data(execute_res_bin)?;
 
-            let action_res_bin = execute_res.data.expect("missing action results data");
-            let action_res = from_slice(&action_res_bin)?;
-
-            PacketAck::Success(action_res)
+            let ack_bin = execute_res.data.expect("missing ack data");
+            from_slice(&ack_bin)?
         },
 
-        // one of actions failed - write an Error ack
-        SubMsgResult::Err(err) => PacketAck::Failed(err.clone()),
+        // one of actions failed after others took effect - write an Error ack
+        SubMsgResult::Err(err) => PacketAck::Error {
+            index: None,
+            code:  redacted_error_code(err),
+            error: err.clone(),
+        },
     };
 
     Ok(Response::new()
         .add_attribute("method", "after_actions")
-        .add_attribute("success", res.is_ok().to_string())
+        .add_attribute("success", matches!(ack, PacketAck::Success(_)).to_string())
         // wasmd will interpret this data field as the ack, overriding the ack
         // emitted in the ibc_packet_receive entry point
         .set_data(to_binary(&ack)?))
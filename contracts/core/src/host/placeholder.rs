@@ -0,0 +1,230 @@
+use {
+    crate::{
+        error::{Error, Result},
+        utils::Json,
+    },
+    cosmwasm_std::{from_slice, to_vec, Binary},
+    ics999::{Action, ActionResult},
+};
+
+const PLACEHOLDER_START: &str = "{{results.";
+const PLACEHOLDER_END: &str = "}}";
+
+/// Substitute placeholders in the action with values from the results of the
+/// actions executed before it. Only the recipient of `Transfer` actions and the
+/// message of `Execute` actions may contain placeholders.
+pub fn resolve_placeholders(action: Action, results: &[ActionResult]) -> Result<Action> {
+    match action {
+        Action::Transfer {
+            denom,
+            amount,
+            recipient,
+        } => Ok(Action::Transfer {
+            denom,
+            amount,
+            recipient: recipient
+                .map(|recipient| substitute(&recipient, results, false))
+                .transpose()?,
+        }),
+
+        Action::Execute {
+            msg,
+            funds,
+        } => {
+            // the message is usually, but not necessarily, JSON. if it's not
+            // text at all, it can't contain placeholders
+            let msg = match std::str::from_utf8(&msg) {
+                Ok(text) => substitute(text, results, true)?.into_bytes().into(),
+                Err(_) => msg,
+            };
+
+            Ok(Action::Execute {
+                msg,
+                funds,
+            })
+        },
+
+        action => Ok(action),
+    }
+}
+
+/// Replace each `{{results.<index>.<path>}}` in the text with the value it
+/// refers to. If the text is JSON, the value is escaped such that it can be
+/// placed inside a JSON string.
+fn substitute(text: &str, results: &[ActionResult], escape: bool) -> Result<String> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = rest.find(PLACEHOLDER_END).ok_or_else(|| Error::InvalidPlaceholder {
+            placeholder: rest.into(),
+            reason:      "placeholder is not closed".into(),
+        })?;
+
+        let placeholder = &rest[..end + PLACEHOLDER_END.len()];
+        let path = &rest[PLACEHOLDER_START.len()..end];
+
+        let value = resolve(path, results).map_err(|reason| Error::InvalidPlaceholder {
+            placeholder: placeholder.into(),
+            reason,
+        })?;
+
+        if escape {
+            // serializing a string gives its escaped form wrapped in quotes
+            let quoted = String::from_utf8(to_vec(&value)?).expect("JSON is always UTF-8");
+            output.push_str(&quoted[1..quoted.len() - 1]);
+        } else {
+            output.push_str(&value);
+        }
+
+        rest = &rest[placeholder.len()..];
+    }
+
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+/// Find the value at the path, which starts with the index of the result,
+/// followed by the object keys and array indexes in the JSON form of it.
+///
+/// Binary values, such as the data returned by `Execute` actions and the
+/// responses of queries, are decoded as JSON if the path continues into them.
+fn resolve(path: &str, results: &[ActionResult]) -> std::result::Result<String, String> {
+    let mut keys = path.split('.');
+
+    let index = keys.next().unwrap_or_default();
+    let result = index
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| results.get(index))
+        .ok_or_else(|| format!("there is no result of an earlier action at index `{index}`"))?;
+
+    let mut json = to_json(&to_vec(result).map_err(|err| err.to_string())?)?;
+
+    for key in keys {
+        if let Json::Scalar(text) = &json {
+            let bytes = Binary::from_base64(text)
+                .map_err(|_| format!("value before `{key}` is neither an object nor an array"))?;
+            json = to_json(&bytes)?;
+        }
+
+        json = json.get(key).cloned().ok_or_else(|| format!("no value found at `{key}`"))?;
+    }
+
+    match json {
+        Json::Scalar(value) => Ok(value),
+        _ => Err("value is not a string, number, boolean or null".into()),
+    }
+}
+
+fn to_json(bytes: &[u8]) -> std::result::Result<Json, String> {
+    from_slice(bytes).map_err(|err| err.to_string())
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{to_binary, Uint128};
+
+    use super::*;
+
+    fn results() -> Vec<ActionResult> {
+        vec![
+            ActionResult::RegisterAccount {
+                address: "ica".into(),
+            },
+            ActionResult::Execute {
+                data: Some(br#"{"return_amount":"12345","memo":"say \"hi\""}"#.into()),
+            },
+            ActionResult::Query {
+                response: to_binary(&["uatom", "uosmo"]).unwrap(),
+            },
+        ]
+    }
+
+    fn execute(msg: &str) -> Action {
+        Action::Execute {
+            msg:   msg.as_bytes().into(),
+            funds: vec![],
+        }
+    }
+
+    #[test]
+    fn resolving_placeholders() {
+        // values from register account results, execute data and query responses
+        let action = resolve_placeholders(
+            execute(
+                r#"{"swap":{"to":"{{results.0.register_account.address}}","amount":"{{results.1.execute.data.return_amount}}","denom":"{{results.2.query.response.1}}"}}"#,
+            ),
+            &results(),
+        )
+        .unwrap();
+        assert_eq!(
+            action,
+            execute(r#"{"swap":{"to":"ica","amount":"12345","denom":"uosmo"}}"#),
+        );
+
+        // values are escaped in execute messages, but not in transfer recipients
+        let action = resolve_placeholders(
+            execute(r#"{"memo":"{{results.1.execute.data.memo}}"}"#),
+            &results(),
+        )
+        .unwrap();
+        assert_eq!(action, execute(r#"{"memo":"say \"hi\""}"#));
+
+        let action = resolve_placeholders(
+            Action::Transfer {
+                denom:     "uatom".into(),
+                amount:    Uint128::new(100),
+                recipient: Some("{{results.1.execute.data.memo}}".into()),
+            },
+            &results(),
+        )
+        .unwrap();
+        assert_eq!(action, Action::Transfer {
+            denom:     "uatom".into(),
+            amount:    Uint128::new(100),
+            recipient: Some(r#"say "hi""#.into()),
+        });
+
+        // binary values are substituted as they are, in base64
+        let action = resolve_placeholders(
+            execute(r#"{"raw":"{{results.2.query.response}}"}"#),
+            &results(),
+        )
+        .unwrap();
+        assert_eq!(action, execute(r#"{"raw":"WyJ1YXRvbSIsInVvc21vIl0="}"#));
+
+        // text without placeholders is unchanged
+        let action = resolve_placeholders(execute("{{results}}"), &results()).unwrap();
+        assert_eq!(action, execute("{{results}}"));
+    }
+
+    #[test]
+    fn rejecting_invalid_placeholders() {
+        let err = |msg: &str| resolve_placeholders(execute(msg), &results()).unwrap_err();
+
+        // referring to the result of a later action
+        assert_eq!(err("{{results.3.execute.data}}"), Error::InvalidPlaceholder {
+            placeholder: "{{results.3.execute.data}}".into(),
+            reason:      "there is no result of an earlier action at index `3`".into(),
+        });
+
+        // the path doesn't exist
+        assert!(matches!(
+            err("{{results.0.register_account.salt}}"),
+            Error::InvalidPlaceholder { .. },
+        ));
+
+        // the value is not a scalar
+        assert!(matches!(err("{{results.0}}"), Error::InvalidPlaceholder { .. }));
+
+        // the placeholder is not closed
+        assert!(matches!(err("{{results.0.register_account"), Error::InvalidPlaceholder { .. }));
+    }
+}
@@ -1,19 +1,39 @@
+pub mod admin;
+pub mod assertion;
 #[cfg(not(feature = "library"))]
 pub mod contract;
 pub mod controller;
 pub mod error;
+pub mod fee;
+pub mod forward;
 pub mod handshake;
 pub mod host;
+pub mod migrations;
 pub mod msg;
+pub mod pause;
 pub mod query;
+pub mod rate_limit;
+pub mod recovery;
 pub mod state;
+pub mod timeout;
+pub mod token_backend;
 pub mod transfer;
 pub mod utils;
 
+/// The custom message type of the contract's responses: the chain bindings'
+/// tokenfactory messages if the `bindings` feature is enabled, in which case
+/// the `Bindings` tokenfactory flavor can be selected
+#[cfg(feature = "bindings")]
+pub type CustomMsg = token_backend::TokenFactoryMsg;
+#[cfg(not(feature = "bindings"))]
+pub type CustomMsg = cosmwasm_std::Empty;
+
 pub const CONTRACT_NAME:    &str = "crates.io:one-core";
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 // reply IDs
-const AFTER_ACTION:      u64 = 1111;
-const AFTER_ALL_ACTIONS: u64 = 2222;
-const AFTER_CALLBACK:    u64 = 3333;
+const AFTER_ACTION:          u64 = 1111;
+const AFTER_ALL_ACTIONS:     u64 = 2222;
+const AFTER_CALLBACK:        u64 = 3333;
+const AFTER_ISOLATED_ACTION: u64 = 4444;
+const AFTER_SEND_PACKET:     u64 = 5555;
@@ -0,0 +1,129 @@
+mod v0_1_0;
+
+use {
+    crate::{
+        error::{Error, Result},
+        msg::MigrateMsg,
+        CustomMsg,
+        CONTRACT_NAME, CONTRACT_VERSION,
+    },
+    cosmwasm_std::{DepsMut, Env, Response, StdError, StdResult},
+    semver::Version,
+};
+
+/// A state migration, which upgrades the contract store from the layout of the
+/// previous version to that of the version it is registered under.
+type MigrateFn = fn(DepsMut, &Env, &MigrateMsg) -> Result<()>;
+
+/// All state migrations, in ascending order of version.
+///
+/// When upgrading the contract, every migration whose version is newer than the
+/// stored version, but not newer than the version being migrated to, is run in
+/// order. To change the layout of CONFIG, ACCOUNTS, DENOM_TRACES, ACTIVE_CHANNELS
+/// or any other store item, add a step here.
+const MIGRATIONS: &[(&str, MigrateFn)] = &[
+    ("0.1.0", v0_1_0::migrate),
+];
+
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response<CustomMsg>> {
+    let stored = cw2::get_contract_version(deps.storage)?;
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(Error::IncorrectContract {
+            actual:   stored.contract,
+            expected: CONTRACT_NAME.into(),
+        });
+    }
+
+    let from = parse_version(&stored.version)?;
+    let to = parse_version(CONTRACT_VERSION)?;
+
+    if from > to {
+        return Err(Error::Downgrade {
+            from: from.to_string(),
+            to:   to.to_string(),
+        });
+    }
+
+    let mut res = Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", from.to_string())
+        .add_attribute("to_version", to.to_string());
+
+    for (version, migrate_fn) in MIGRATIONS {
+        let version = parse_version(version)?;
+
+        if from < version && version <= to {
+            migrate_fn(deps.branch(), &env, &msg)?;
+            res = res.add_attribute("migration", version.to_string());
+        }
+    }
+
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(res)
+}
+
+fn parse_version(version: &str) -> StdResult<Version> {
+    version
+        .parse()
+        .map_err(|err| StdError::generic_err(format!("invalid version `{version}`: {err}")))
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    use super::*;
+
+    const MIGRATE_MSG: MigrateMsg = MigrateMsg {
+        owner:   None,
+        escrows: None,
+    };
+
+    #[test]
+    fn migrations_are_ordered() {
+        let versions = MIGRATIONS
+            .iter()
+            .map(|(version, _)| parse_version(version).unwrap())
+            .collect::<Vec<_>>();
+
+        let current = parse_version(CONTRACT_VERSION).unwrap();
+
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(versions.iter().all(|version| *version <= current));
+    }
+
+    #[test]
+    fn rejecting_incorrect_contract() {
+        let mut deps = mock_dependencies();
+
+        cw2::set_contract_version(deps.as_mut().storage, "crates.io:cw20-base", "0.0.0").unwrap();
+
+        let err = migrate(deps.as_mut(), mock_env(), MIGRATE_MSG).unwrap_err();
+        assert!(matches!(err, Error::IncorrectContract { .. }));
+    }
+
+    #[test]
+    fn rejecting_downgrade() {
+        let mut deps = mock_dependencies();
+
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+
+        let err = migrate(deps.as_mut(), mock_env(), MIGRATE_MSG).unwrap_err();
+        assert!(matches!(err, Error::Downgrade { .. }));
+    }
+
+    #[test]
+    fn migrating_from_same_version() {
+        let mut deps = mock_dependencies();
+
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+
+        // no migration step should be run, so an owner is not needed
+        let res = migrate(deps.as_mut(), mock_env(), MIGRATE_MSG).unwrap();
+        assert!(res.attributes.iter().all(|attr| attr.key != "migration"));
+    }
+}
@@ -0,0 +1,347 @@
+use {
+    crate::{
+        error::{Error, Result},
+        msg::{Config, MigrateMsg, TokenFactoryFlavor, VoucherBackend},
+        state::{
+            ACTIVE_CHANNELS, CHANNEL_CONNECTIONS, CONFIG, DENOM_TRACES, ESCROWS, VOUCHER_SUPPLIES,
+        },
+        transfer::parse_cw20_denom,
+    },
+    cosmwasm_schema::cw_serde,
+    cosmwasm_std::{Addr, DepsMut, Env, Order, StdResult, Storage, Uint128},
+    cw20::{BalanceResponse, Cw20QueryMsg},
+    cw_storage_plus::Item,
+    std::collections::BTreeMap,
+};
+
+#[cw_serde]
+struct ConfigV0_0_0 {
+    default_account_code_id: u64,
+    default_timeout_secs:    u64,
+}
+
+const CONFIG_V0_0_0: Item<ConfigV0_0_0> = Item::new("cfg");
+
+const MAX_TIMEOUT_SECS:           u64 = 604_800; // 1 week
+const DEFAULT_CALLBACK_GAS_LIMIT: u64 = 1_000_000;
+const MAX_CALLBACK_GAS_LIMIT:     u64 = 5_000_000;
+
+/// Migrate from v0.0.0. Each change to the store layout is a step of its own:
+/// see the individual steps for details.
+///
+/// ACCOUNTS and DENOM_TRACES are unchanged.
+pub fn migrate(mut deps: DepsMut, env: &Env, msg: &MigrateMsg) -> Result<()> {
+    set_owner(deps.branch(), msg)?;
+    index_channels(deps.storage)?;
+    migrate_config(deps.storage)?;
+    seed_escrows(deps.branch(), &env.contract.address, msg)?;
+    seed_voucher_supplies(deps)?;
+
+    Ok(())
+}
+
+/// v0.0.0 did not have an owner. Initialize it with the one provided in the
+/// migrate message.
+fn set_owner(deps: DepsMut, msg: &MigrateMsg) -> Result<()> {
+    let owner = msg.owner.as_deref().ok_or(Error::OwnerMissing)?;
+
+    cw_ownable::initialize_owner(deps.storage, deps.api, Some(owner))?;
+
+    Ok(())
+}
+
+/// v0.0.0 did not index connections by channel. Build the index from the
+/// active channels.
+fn index_channels(store: &mut dyn Storage) -> Result<()> {
+    let active_channels = ACTIVE_CHANNELS
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (connection_id, endpoint) in active_channels {
+        CHANNEL_CONNECTIONS.save(store, &endpoint.channel_id, &connection_id)?;
+    }
+
+    Ok(())
+}
+
+/// - v0.0.0 only supported Osmosis' tokenfactory for vouchers. Add the voucher
+///   backend to the config accordingly.
+/// - v0.0.0 did not bound packet timeouts. Add the max timeout to the config,
+///   raised to the default timeout if that is higher.
+/// - v0.0.0 did not limit the gas of packet callbacks. Add the default and max
+///   callback gas limits to the config.
+fn migrate_config(store: &mut dyn Storage) -> Result<()> {
+    let cfg = CONFIG_V0_0_0.load(store)?;
+
+    CONFIG.save(store, &Config {
+        default_account_code_id:    cfg.default_account_code_id,
+        default_timeout_secs:       cfg.default_timeout_secs,
+        max_timeout_secs:           cfg.default_timeout_secs.max(MAX_TIMEOUT_SECS),
+        voucher_backend:            VoucherBackend::TokenFactory {
+            flavor: TokenFactoryFlavor::Osmosis,
+        },
+        default_callback_gas_limit: DEFAULT_CALLBACK_GAS_LIMIT,
+        max_callback_gas_limit:     MAX_CALLBACK_GAS_LIMIT,
+    })?;
+
+    Ok(())
+}
+
+/// v0.0.0 did not track escrows. Initialize them with the ones provided in the
+/// migrate message.
+///
+/// Escrows not provided would lock the tokens, so the list is required if any
+/// channel is active. The contract must hold the total escrowed of each token.
+fn seed_escrows(deps: DepsMut, contract: &Addr, msg: &MigrateMsg) -> Result<()> {
+    let escrows = match &msg.escrows {
+        Some(escrows) => escrows,
+        None if ACTIVE_CHANNELS.is_empty(deps.storage) => return Ok(()),
+        None => return Err(Error::EscrowsMissing),
+    };
+
+    let mut totals = BTreeMap::<&str, Uint128>::new();
+
+    for escrow in escrows {
+        *totals.entry(&escrow.denom).or_default() += escrow.amount;
+
+        ESCROWS.save(deps.storage, (&escrow.channel_id, &escrow.denom), &escrow.amount)?;
+    }
+
+    for (denom, escrowed) in totals {
+        let balance = match parse_cw20_denom(denom) {
+            Some(contract_addr) => {
+                deps.querier
+                    .query_wasm_smart::<BalanceResponse>(contract_addr, &Cw20QueryMsg::Balance {
+                        address: contract.into(),
+                    })?
+                    .balance
+            },
+            None => deps.querier.query_balance(contract, denom)?.amount,
+        };
+
+        if escrowed > balance {
+            return Err(Error::EscrowExceedsBalance {
+                denom: denom.into(),
+                escrowed,
+                balance,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// v0.0.0 did not track voucher supplies. Initialize them with the bank
+/// supplies of the vouchers, which are only ever minted by this contract.
+fn seed_voucher_supplies(deps: DepsMut) -> Result<()> {
+    let vouchers = DENOM_TRACES
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for denom in vouchers {
+        let supply = deps.querier.query_supply(&denom)?;
+        VOUCHER_SUPPLIES.save(deps.storage, &denom, &supply.amount)?;
+    }
+
+    Ok(())
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coin,
+        testing::{mock_dependencies, mock_env},
+        Addr, IbcEndpoint, Uint128,
+    };
+
+    use super::*;
+    use crate::{
+        msg::ChannelEscrow,
+        migrations,
+        transfer::TraceItem,
+        CONTRACT_NAME, CONTRACT_VERSION,
+    };
+
+    const MIGRATE_MSG: MigrateMsg = MigrateMsg {
+        owner:   None,
+        escrows: None,
+    };
+
+    #[test]
+    fn setting_owner() {
+        let mut deps = mock_dependencies();
+
+        // owner must be provided
+        {
+            let err = set_owner(deps.as_mut(), &MIGRATE_MSG).unwrap_err();
+            assert_eq!(err, Error::OwnerMissing);
+        }
+
+        set_owner(deps.as_mut(), &MigrateMsg {
+            owner: Some("larry".into()),
+            ..MIGRATE_MSG
+        })
+        .unwrap();
+
+        let ownership = cw_ownable::get_ownership(deps.as_ref().storage).unwrap();
+        assert_eq!(ownership.owner, Some(Addr::unchecked("larry")));
+    }
+
+    #[test]
+    fn indexing_channels() {
+        let mut deps = mock_dependencies();
+
+        ACTIVE_CHANNELS
+            .save(deps.as_mut().storage, "connection-0", &IbcEndpoint {
+                port_id:    "wasm.one-core".into(),
+                channel_id: "channel-3".into(),
+            })
+            .unwrap();
+
+        index_channels(deps.as_mut().storage).unwrap();
+
+        let connection_id = CHANNEL_CONNECTIONS.load(deps.as_ref().storage, "channel-3").unwrap();
+        assert_eq!(connection_id, "connection-0");
+    }
+
+    #[test]
+    fn migrating_config() {
+        let mut deps = mock_dependencies();
+
+        CONFIG_V0_0_0
+            .save(deps.as_mut().storage, &ConfigV0_0_0 {
+                default_account_code_id: 1,
+                default_timeout_secs:    300,
+            })
+            .unwrap();
+
+        migrate_config(deps.as_mut().storage).unwrap();
+
+        let cfg = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(cfg, Config {
+            default_account_code_id:    1,
+            default_timeout_secs:       300,
+            max_timeout_secs:           MAX_TIMEOUT_SECS,
+            voucher_backend:            VoucherBackend::TokenFactory {
+                flavor: TokenFactoryFlavor::Osmosis,
+            },
+            default_callback_gas_limit: DEFAULT_CALLBACK_GAS_LIMIT,
+            max_callback_gas_limit:     MAX_CALLBACK_GAS_LIMIT,
+        });
+
+        // the max timeout is raised to a default timeout longer than it
+        CONFIG_V0_0_0
+            .save(deps.as_mut().storage, &ConfigV0_0_0 {
+                default_account_code_id: 1,
+                default_timeout_secs:    MAX_TIMEOUT_SECS + 1,
+            })
+            .unwrap();
+
+        migrate_config(deps.as_mut().storage).unwrap();
+
+        let cfg = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(cfg.max_timeout_secs, MAX_TIMEOUT_SECS + 1);
+    }
+
+    #[test]
+    fn seeding_escrows() {
+        let mut deps = mock_dependencies();
+        let contract = mock_env().contract.address;
+
+        let escrow = |channel_id: &str, amount: u128| ChannelEscrow {
+            channel_id: channel_id.into(),
+            denom:      "uosmo".into(),
+            amount:     Uint128::new(amount),
+        };
+
+        // without active channels, there is nothing to escrow
+        seed_escrows(deps.as_mut(), &contract, &MIGRATE_MSG).unwrap();
+
+        ACTIVE_CHANNELS
+            .save(deps.as_mut().storage, "connection-0", &IbcEndpoint {
+                port_id:    "wasm.one-core".into(),
+                channel_id: "channel-3".into(),
+            })
+            .unwrap();
+        deps.querier.update_balance(&contract, vec![coin(12345, "uosmo")]);
+
+        // with active channels, escrows must be provided
+        {
+            let err = seed_escrows(deps.as_mut(), &contract, &MIGRATE_MSG).unwrap_err();
+            assert_eq!(err, Error::EscrowsMissing);
+        }
+
+        // escrows of all channels combined can't exceed the contract's balance
+        {
+            let err = seed_escrows(deps.as_mut(), &contract, &MigrateMsg {
+                escrows: Some(vec![escrow("channel-3", 12345), escrow("channel-4", 1)]),
+                ..MIGRATE_MSG
+            })
+            .unwrap_err();
+            assert_eq!(err, Error::EscrowExceedsBalance {
+                denom:    "uosmo".into(),
+                escrowed: Uint128::new(12346),
+                balance:  Uint128::new(12345),
+            });
+        }
+
+        seed_escrows(deps.as_mut(), &contract, &MigrateMsg {
+            escrows: Some(vec![escrow("channel-3", 12000), escrow("channel-4", 345)]),
+            ..MIGRATE_MSG
+        })
+        .unwrap();
+
+        let escrowed = ESCROWS.load(deps.as_ref().storage, ("channel-3", "uosmo")).unwrap();
+        assert_eq!(escrowed, Uint128::new(12000));
+
+        let escrowed = ESCROWS.load(deps.as_ref().storage, ("channel-4", "uosmo")).unwrap();
+        assert_eq!(escrowed, Uint128::new(345));
+    }
+
+    #[test]
+    fn seeding_voucher_supplies() {
+        let mut deps = mock_dependencies();
+
+        // a voucher that has been minted
+        DENOM_TRACES
+            .save(deps.as_mut().storage, "factory/one-core/voucher", &TraceItem {
+                base_denom: "uatom".into(),
+                path:       vec![],
+            })
+            .unwrap();
+        deps.querier.update_balance("jake", vec![coin(888, "factory/one-core/voucher")]);
+
+        seed_voucher_supplies(deps.as_mut()).unwrap();
+
+        let supply =
+            VOUCHER_SUPPLIES.load(deps.as_ref().storage, "factory/one-core/voucher").unwrap();
+        assert_eq!(supply, Uint128::new(888));
+    }
+
+    #[test]
+    fn migrating() {
+        let mut deps = mock_dependencies();
+
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.0.0").unwrap();
+
+        CONFIG_V0_0_0
+            .save(deps.as_mut().storage, &ConfigV0_0_0 {
+                default_account_code_id: 1,
+                default_timeout_secs:    300,
+            })
+            .unwrap();
+
+        // the step is run by the framework when migrating from v0.0.0
+        let res = migrations::migrate(deps.as_mut(), mock_env(), MigrateMsg {
+            owner: Some("larry".into()),
+            ..MIGRATE_MSG
+        })
+        .unwrap();
+        assert!(res.attributes.iter().any(|attr| attr.key == "migration" && attr.value == "0.1.0"));
+
+        let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+    }
+}
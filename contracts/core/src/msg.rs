@@ -1,8 +1,13 @@
 use {
     crate::transfer::TraceItem,
     cosmwasm_schema::{cw_serde, QueryResponses},
-    cosmwasm_std::{HexBinary, IbcEndpoint, IbcTimeout},
-    ics999::{Action, Trace},
+    cosmwasm_std::{
+        Binary, Coin, Decimal, HexBinary, IbcEndpoint, IbcTimeout, Timestamp, Uint128,
+    },
+    cw20::Cw20ReceiveMsg,
+    cw_ownable::{cw_ownable_execute, cw_ownable_query},
+    ics999::{Action, ActionType, CallbackMsg, PacketData, PacketOutcome, Trace},
+    std::fmt,
 };
 
 #[cw_serde]
@@ -10,20 +15,145 @@ pub struct Config {
     /// Code ID of the one-account contract
     pub default_account_code_id: u64,
 
-    /// The default timeout (in seconds) if the user does not provide a timeout
-    /// timestamp
+    /// The default timeout (in seconds) if the user does not provide a timeout,
+    /// and the connection has no default timeout of its own
     pub default_timeout_secs: u64,
+
+    /// The furthest in the future (in seconds) a packet's timeout can be, so
+    /// that funds can't be locked in escrow indefinitely
+    pub max_timeout_secs: u64,
+
+    /// How voucher tokens are created. Can't be changed after instantiation.
+    pub voucher_backend: VoucherBackend,
+
+    /// The gas limit of the callback to the controller upon the packet's
+    /// acknowledgement or timeout, if the user does not provide one
+    pub default_callback_gas_limit: u64,
+
+    /// The highest callback gas limit a user may request
+    pub max_callback_gas_limit: u64,
+}
+
+#[cw_serde]
+pub enum VoucherBackend {
+    /// Native tokens created using the tokenfactory module
+    TokenFactory {
+        flavor: TokenFactoryFlavor,
+    },
+
+    /// For chains without tokenfactory: an instance of the cw20-base contract
+    /// is created for each denom trace, with one-core being the minter. The
+    /// voucher denom is `cw20:{token_addr}`.
+    Cw20 {
+        code_id: u64,
+    },
+}
+
+/// The chain's variant of the tokenfactory module, which differ in messages,
+/// fee params and whether tokens can be minted directly to the recipient
+#[cw_serde]
+pub enum TokenFactoryFlavor {
+    Osmosis,
+    Neutron,
+    Injective,
+    Sei,
+    Kujira,
+
+    /// Tokenfactory exposed through `CosmosMsg::Custom` chain bindings instead
+    /// of stargate messages. Only available if the contract is built with the
+    /// `bindings` feature.
+    #[cfg(feature = "bindings")]
+    Bindings,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    /// The contract owner.
+    ///
+    /// Only used when migrating from a version that predates the owner role,
+    /// in which case it must be provided. Ignored otherwise.
+    pub owner: Option<String>,
+
+    /// The amount of each token native to this chain that is held in escrow
+    /// for each channel.
+    ///
+    /// Only used when migrating from a version that predates escrow tracking,
+    /// in which case it must be provided if any channel is active, and the
+    /// total of each token can't exceed the contract's balance. Ignored
+    /// otherwise.
+    ///
+    /// Tokens not included are locked: they can't be released when vouchers
+    /// are sent back, until the same amount is escrowed again.
+    pub escrows: Option<Vec<ChannelEscrow>>,
+}
+
+#[cw_serde]
+pub struct ChannelEscrow {
+    pub channel_id: String,
+    pub denom:      String,
+    pub amount:     Uint128,
 }
 
+#[cw_ownable_execute]
 #[cw_serde]
 pub enum ExecuteMsg {
     // ----------------------- USED ON CONTROLLER CHAIN ------------------------
 
-    /// Send a packet consisting of a series of actions
+    /// Send a packet consisting of a series of actions.
+    ///
+    /// Optionally, fees can be attached to incentivize relayers. These must be
+    /// sent along with the funds to be transferred.
+    ///
+    /// If `allow_failure` is set, the actions are not executed atomically: a
+    /// failed action is skipped, and the rest of them proceed.
+    ///
+    /// The callback to the sender upon the packet's acknowledgement or timeout
+    /// runs with a limited amount of gas, so that it can't block the packet's
+    /// completion. If not provided, the default limit is used. It can't exceed
+    /// the maximum in the config.
+    ///
+    /// The callback can be disabled or redirected to another contract, and
+    /// carry data for correlating it with the dispatch, using `callback`.
+    ///
+    /// If a timeout is not provided, the connection's default is used. It
+    /// can't be further in the future than the maximum in the config.
     Dispatch {
-        connection_id: String,
-        actions:       Vec<Action>,
-        timeout:       Option<IbcTimeout>,
+        connection_id:      String,
+        actions:            Vec<Action>,
+        timeout:            Option<Timeout>,
+        fee:                Option<PacketFee>,
+        #[serde(default)]
+        allow_failure:      bool,
+        callback_gas_limit: Option<u64>,
+        callback:           Option<CallbackOptions>,
+    },
+
+    /// Send cw20 tokens via ICS-999. The hook message must be a `Cw20HookMsg`.
+    ///
+    /// In the actions, the token is to be referred to by the denom
+    /// `cw20:{token_addr}`.
+    Receive(Cw20ReceiveMsg),
+
+    /// Deliver a callback that failed to the controller again.
+    ///
+    /// Can be invoked by anyone. The callback is not gas-limited, and if it
+    /// fails again, it remains available for retrying.
+    RetryCallback {
+        channel_id: String,
+        sequence:   u64,
+    },
+
+    // ---------------------- USED ON INTERMEDIATE CHAINS ----------------------
+
+    /// Relay the outcome of a forwarded packet back to the chain it was
+    /// forwarded from again, after the counterparty rejected it, e.g. because
+    /// it couldn't refund the tokens. Meant to be used once whatever caused the
+    /// rejection has been fixed.
+    ///
+    /// Can only be invoked by the contract owner.
+    RetryForwardAck {
+        channel_id: String,
+        sequence:   u64,
     },
 
     // ------------------------ USED ON THE HOST CHAIN -------------------------
@@ -40,12 +170,287 @@ pub enum ExecuteMsg {
     Handle {
         counterparty_endpoint: IbcEndpoint,
         endpoint:              IbcEndpoint,
+        sequence:              u64,
         controller:            String,
         actions:               Vec<Action>,
         traces:                Vec<Trace>,
+        allow_failure:         bool,
+    },
+
+    /// Execute the current action of the packet being handled, in isolation
+    /// from the other actions, so that if it fails, only its own state changes
+    /// are reverted, and the error is returned in full if it is our own.
+    ///
+    /// Can only be invoked by the contract itself.
+    HandleAction {},
+
+    // --------------------------- USED BY THE ADMIN ---------------------------
+
+    /// Update the contract configuration.
+    ///
+    /// Only the fields that are provided will be updated; the rest are left
+    /// unchanged. Can only be invoked by the contract owner.
+    UpdateConfig {
+        default_account_code_id:    Option<u64>,
+        default_timeout_secs:       Option<u64>,
+        max_timeout_secs:           Option<u64>,
+        default_callback_gas_limit: Option<u64>,
+        max_callback_gas_limit:     Option<u64>,
+    },
+
+    /// Retire an ICS-999 channel that has been closed by the counterparty.
+    ///
+    /// ICS-999 channels are not supposed to be closed, but a counterparty chain
+    /// in a catastrophic situation may do it anyways. Retiring the channel frees
+    /// up the connection, so that a new channel can be opened on it. Accounts,
+    /// vouchers and escrows can then be moved over to the new channel using
+    /// `RemapAccounts`, `RemapVouchers` and `RemapEscrows`.
+    RetireChannel {
+        connection_id: String,
+    },
+
+    /// Move interchain accounts registered via the connection's retired
+    /// channel to its new active channel, so that controllers keep their
+    /// accounts.
+    ///
+    /// Accounts are processed in pages; the last controller processed is
+    /// emitted in the `last_controller` event attribute, to be used as
+    /// `start_after` in the next invocation.
+    RemapAccounts {
+        connection_id: String,
+        start_after:   Option<String>,
+        limit:         Option<u32>,
+    },
+
+    /// Rewrite voucher traces that go through the connection's retired channel
+    /// to go through its new active channel, so that vouchers remain
+    /// redeemable.
+    ///
+    /// Refunds of packets that are in-flight on the retired channel are not
+    /// affected, as they undo what was recorded when the packets were sent.
+    /// The exception is tokens forwarded on behalf of a counterparty, which are
+    /// burned upon being returned or not depending on their traces.
+    ///
+    /// Traces are processed in pages; the last denom processed is emitted in
+    /// the `last_denom` event attribute, to be used as `start_after` in the
+    /// next invocation.
+    RemapVouchers {
+        connection_id: String,
+        start_after:   Option<String>,
+        limit:         Option<u32>,
+    },
+
+    /// Move tokens escrowed for the connection's retired channel to its new
+    /// active channel, so that they can be released by packets from the new
+    /// channel.
+    ///
+    /// Refunds of packets that are in-flight on the retired channel release
+    /// tokens from the retired channel's escrow, so this is rejected until
+    /// such packets are complete, e.g. timed out.
+    ///
+    /// Escrows are processed in pages; the last denom processed is emitted in
+    /// the `last_denom` event attribute, to be used as `start_after` in the
+    /// next invocation.
+    RemapEscrows {
+        connection_id: String,
+        start_after:   Option<String>,
+        limit:         Option<u32>,
+    },
+
+    /// Halt dispatching and handling of packets within the given scope, e.g.
+    /// when a counterparty chain is compromised.
+    ///
+    /// Packets received within a paused scope are acknowledged as failed, so
+    /// that the tokens are refunded on the sender chain.
+    Pause(PauseScope),
+
+    /// Resume dispatching and handling of packets within the given scope.
+    Unpause(PauseScope),
+
+    /// Add the funds sent along with this message to the pool used to pay the
+    /// tokenfactory denom creation fee when a new voucher token is created.
+    FundDenomCreationPool {},
+
+    /// Withdraw funds from the denom creation pool. Defaults to sending them
+    /// to the admin if a recipient is not provided.
+    WithdrawDenomCreationPool {
+        amount:    Vec<Coin>,
+        recipient: Option<String>,
+    },
+
+    /// Set the rate limit of a denom on the channel of a connection, or remove
+    /// it if `None` is provided.
+    ///
+    /// The amount of the denom flowing through the channel in each direction
+    /// is tracked over consecutive periods. A transfer that would take the
+    /// flow in the current period over the quota is rejected: on the sender
+    /// chain the dispatch fails; on the receiver chain an error ack is written,
+    /// so that the tokens are refunded to the sender.
+    ///
+    /// For outbound transfers, the denom is the one sent by the user. For
+    /// inbound ones, it is the denom released or minted on this chain.
+    SetRateLimit {
+        connection_id: String,
+        denom:         String,
+        rate_limit:    Option<RateLimit>,
+    },
+
+    /// Set the default timeout (in seconds) of packets sent on the channel of a
+    /// connection, or remove it if `None` is provided, in which case the one in
+    /// the config is used.
+    ///
+    /// Can't exceed the maximum timeout in the config.
+    SetDefaultTimeout {
+        connection_id: String,
+        timeout_secs:  Option<u64>,
+    },
+}
+
+#[cw_serde]
+pub enum Cw20HookMsg {
+    /// Same as `ExecuteMsg::Dispatch`, with the cw20 tokens being sent to be
+    /// transferred in the actions. Relayer fees are not supported.
+    Dispatch {
+        connection_id:      String,
+        actions:            Vec<Action>,
+        timeout:            Option<Timeout>,
+        #[serde(default)]
+        allow_failure:      bool,
+        callback_gas_limit: Option<u64>,
+        callback:           Option<CallbackOptions>,
     },
 }
 
+#[cw_serde]
+pub enum PauseScope {
+    /// All connections and all actions
+    Global {},
+
+    /// All actions dispatched to or received from a specific connection
+    Connection {
+        connection_id: String,
+    },
+
+    /// A specific type of action, e.g. `transfer`, on all connections
+    Action {
+        action: ActionType,
+    },
+}
+
+impl fmt::Display for PauseScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PauseScope::Global {} => write!(f, "ICS-999"),
+            PauseScope::Connection {
+                connection_id,
+            } => write!(f, "connection `{connection_id}`"),
+            PauseScope::Action {
+                action,
+            } => write!(f, "action `{}`", action.as_str()),
+        }
+    }
+}
+
+/// Fees paid to relayers for relaying a packet, in the style of ICS-29.
+///
+/// Once the packet is acknowledged, the receive and ack fees are paid to the
+/// relayer who relayed the ack, and the timeout fee is refunded to the sender.
+/// If the packet times out, the timeout fee is paid to the relayer who relayed
+/// the timeout, and the other two are refunded.
+///
+/// NOTE: Unlike ICS-29, the receive fee is not paid to the relayer who relayed
+/// the packet to the counterparty chain, because the counterparty doesn't
+/// report its address in the ack.
+#[cw_serde]
+#[derive(Default)]
+pub struct PacketFee {
+    pub recv_fee:    Vec<Coin>,
+    pub ack_fee:     Vec<Coin>,
+    pub timeout_fee: Vec<Coin>,
+}
+
+/// How the sender of a packet is to be called back upon the packet's
+/// acknowledgement or timeout.
+#[cw_serde]
+#[derive(Default)]
+pub struct CallbackOptions {
+    /// Don't call back at all, e.g. if the sender is a wallet, or a contract
+    /// that doesn't implement `ControllerExecuteMsg`. The other options are
+    /// ignored if set.
+    #[serde(default)]
+    pub disabled: bool,
+
+    /// The contract to call back instead of the sender
+    pub address: Option<String>,
+
+    /// Opaque data to be echoed back in the `CallbackMsg`
+    pub callback_data: Option<Binary>,
+}
+
+/// When a dispatched packet times out.
+///
+/// Untagged, so that an `IbcTimeout`, which was the only form accepted before
+/// relative timeouts were added, is still accepted as is.
+#[cw_serde]
+#[serde(untagged)]
+pub enum Timeout {
+    /// A number of seconds after the current block time.
+    ///
+    /// Block heights are those of the counterparty chain, which this contract
+    /// can't observe, so they can only be given in absolute terms.
+    Relative {
+        secs: u64,
+    },
+
+    /// A timestamp and/or a block height of the counterparty chain.
+    ///
+    /// If only a block height is given, the latest timestamp allowed is added,
+    /// so that the packet times out at whichever is reached first.
+    Absolute(IbcTimeout),
+}
+
+#[cw_serde]
+pub struct RateLimit {
+    /// Length of each period, in seconds
+    pub period_secs: u64,
+
+    /// Maximum amount that may be received in each period. No limit if `None`.
+    pub max_inflow: Option<Quota>,
+
+    /// Maximum amount that may be sent in each period. No limit if `None`.
+    pub max_outflow: Option<Quota>,
+}
+
+#[cw_serde]
+pub enum Quota {
+    /// A fixed amount of tokens
+    Absolute(Uint128),
+
+    /// A fraction of the token's total supply on this chain at the start of
+    /// the period, e.g. `0.05` for 5%.
+    ///
+    /// NOTE: The quota is zero if the token has no supply at the start of the
+    /// period, e.g. a voucher that is yet to be minted. Use an absolute quota
+    /// for such tokens.
+    SupplyRatio(Decimal),
+}
+
+/// The amount of a denom that has flowed through a channel in the current
+/// rate limit period
+#[cw_serde]
+pub struct Flow {
+    /// The time at which the period ends and the flow resets
+    pub period_end: Timestamp,
+
+    /// The token's total supply at the start of the period
+    pub supply: Uint128,
+
+    pub inflow: Uint128,
+
+    pub outflow: Uint128,
+}
+
+#[cw_ownable_query]
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -95,6 +500,94 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit:       Option<u32>,
     },
+
+    /// Scopes that are currently paused
+    #[returns(PauseStatusResponse)]
+    PauseStatus {},
+
+    /// Rate limit of a denom on a connection, and its flow in the current
+    /// period
+    #[returns(RateLimitResponse)]
+    RateLimit(RateLimitKey),
+
+    /// Funds available for paying the denom creation fee
+    #[returns(Vec<Coin>)]
+    DenomCreationPool {},
+
+    /// Fees escrowed for a packet that has been sent but not yet acknowledged
+    /// or timed out
+    #[returns(PacketFee)]
+    PacketFee {
+        channel_id: String,
+        sequence:   u64,
+    },
+
+    /// Iterate rate limits on all connections
+    #[returns(Vec<RateLimitResponse>)]
+    RateLimits {
+        start_after: Option<RateLimitKey>,
+        limit:       Option<u32>,
+    },
+
+    /// A packet sent by this contract, whether pending or complete
+    #[returns(PacketInfo)]
+    Packet(PacketKey),
+
+    /// Iterate packets sent on behalf of a controller that are yet to be
+    /// complete
+    #[returns(Vec<PacketInfo>)]
+    PendingPackets {
+        controller:  String,
+        start_after: Option<PacketKey>,
+        limit:       Option<u32>,
+    },
+
+    /// A callback to the controller of a packet that failed, and can be
+    /// retried using `ExecuteMsg::RetryCallback`
+    #[returns(FailedCallback)]
+    FailedCallback(PacketKey),
+
+    /// Iterate failed callbacks of packets on all channels
+    #[returns(Vec<FailedCallback>)]
+    FailedCallbacks {
+        start_after: Option<PacketKey>,
+        limit:       Option<u32>,
+    },
+
+    /// A packet relaying the outcome of a forwarded packet that was rejected
+    /// by the counterparty, and can be sent again using
+    /// `ExecuteMsg::RetryForwardAck`
+    #[returns(FailedForwardAck)]
+    FailedForwardAck(PacketKey),
+
+    /// Iterate rejected packets relaying outcomes of forwarded packets on all
+    /// channels
+    #[returns(Vec<FailedForwardAck>)]
+    FailedForwardAcks {
+        start_after: Option<PacketKey>,
+        limit:       Option<u32>,
+    },
+
+    /// Amount of a token native to this chain that is held in escrow for a
+    /// channel, i.e. the most that can be released by packets from it
+    #[returns(Coin)]
+    EscrowBalance {
+        channel_id: String,
+        denom:      String,
+    },
+
+    /// Amount of a voucher minted by this contract that has not been burned
+    #[returns(Coin)]
+    VoucherSupply {
+        denom: String,
+    },
+
+    /// Default timeout (in seconds) of packets sent on a connection, taking
+    /// into account the connection's own default, if any
+    #[returns(u64)]
+    DefaultTimeout {
+        connection_id: String,
+    },
 }
 
 #[cw_serde]
@@ -120,3 +613,96 @@ pub struct ActiveChannelResponse {
     pub connection_id: String,
     pub endpoint:      IbcEndpoint,
 }
+
+#[cw_serde]
+pub struct PauseStatusResponse {
+    pub global:      bool,
+    pub connections: Vec<String>,
+    pub actions:     Vec<String>,
+}
+
+#[cw_serde]
+pub struct PacketKey {
+    pub channel_id: String,
+    pub sequence:   u64,
+}
+
+#[cw_serde]
+pub struct PacketInfo {
+    pub channel_id:    String,
+    pub sequence:      u64,
+    pub connection_id: String,
+    pub controller:    String,
+
+    /// Types of the actions in the packet, e.g. `transfer` or `execute`
+    pub actions: Vec<String>,
+
+    /// Coins escrowed by this chain for the transfers in the packet
+    pub escrowed: Vec<Coin>,
+
+    /// Vouchers burned by this chain for the transfers in the packet
+    pub burned: Vec<Coin>,
+
+    pub timeout: IbcTimeout,
+    pub status:  PacketStatus,
+}
+
+#[cw_serde]
+pub enum PacketStatus {
+    /// The packet has not been acknowledged or timed out yet, or it has been
+    /// acknowledged, but is waiting for the outcomes of the packets it forwarded
+    Pending {},
+
+    /// The packet's lifecycle is complete, with the type of its outcome, e.g.
+    /// `success` or `timeout`
+    Complete {
+        outcome: String,
+    },
+}
+
+#[cw_serde]
+pub struct FailedCallback {
+    /// The contract that was called back
+    pub address: String,
+
+    /// The message it was called back with, which includes the packet's
+    /// channel and sequence
+    pub msg: CallbackMsg,
+
+    /// The error it failed with, as redacted by wasmd
+    pub error: String,
+}
+
+#[cw_serde]
+pub struct FailedForwardAck {
+    /// The channel the packet relaying the outcome was sent through, which is
+    /// the one the original packet arrived from
+    pub channel_id: String,
+
+    /// Sequence of the packet relaying the outcome
+    pub sequence: u64,
+
+    /// The packet relaying the outcome, to be sent again
+    pub packet_data: PacketData,
+
+    /// The outcome of the packet relaying the outcome, i.e. why the
+    /// counterparty rejected it
+    pub outcome: PacketOutcome,
+}
+
+#[cw_serde]
+pub struct RateLimitKey {
+    pub connection_id: String,
+    pub denom:         String,
+}
+
+#[cw_serde]
+pub struct RateLimitResponse {
+    pub connection_id: String,
+    pub denom:         String,
+    pub rate_limit:    RateLimit,
+
+    /// `None` if nothing has flowed through the channel since the rate limit
+    /// was set. The flow may be from a period that has already ended.
+    pub flow: Option<Flow>,
+}
@@ -0,0 +1,215 @@
+use {
+    crate::{
+        error::{Error, Result},
+        msg::{PauseScope, PauseStatusResponse},
+        state::{GLOBAL_PAUSE, PAUSED_ACTIONS, PAUSED_CONNECTIONS},
+        CustomMsg,
+    },
+    cosmwasm_std::{DepsMut, Empty, MessageInfo, Order, Response, StdResult, Storage},
+    ics999::Action,
+};
+
+pub fn set_paused(
+    deps:   DepsMut,
+    info:   MessageInfo,
+    scope:  PauseScope,
+    paused: bool,
+) -> Result<Response<CustomMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    match &scope {
+        PauseScope::Global {} => {
+            GLOBAL_PAUSE.save(deps.storage, &paused)?;
+        },
+        PauseScope::Connection {
+            connection_id,
+        } => {
+            if paused {
+                PAUSED_CONNECTIONS.save(deps.storage, connection_id, &Empty {})?;
+            } else {
+                PAUSED_CONNECTIONS.remove(deps.storage, connection_id);
+            }
+        },
+        PauseScope::Action {
+            action,
+        } => {
+            if paused {
+                PAUSED_ACTIONS.save(deps.storage, action.as_str(), &Empty {})?;
+            } else {
+                PAUSED_ACTIONS.remove(deps.storage, action.as_str());
+            }
+        },
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", if paused { "pause" } else { "unpause" })
+        .add_attribute("scope", scope.to_string()))
+}
+
+/// Assert that neither the protocol as a whole, nor the connection, nor any of
+/// the given actions is paused, including those nested in `Forward` actions,
+/// whose tokens pass through this chain.
+pub fn assert_not_paused(
+    store:         &dyn Storage,
+    connection_id: &str,
+    actions:       &[Action],
+) -> Result<()> {
+    if GLOBAL_PAUSE.may_load(store)?.unwrap_or(false) {
+        return Err(Error::Paused {
+            scope: PauseScope::Global {},
+        });
+    }
+
+    if PAUSED_CONNECTIONS.has(store, connection_id) {
+        return Err(Error::Paused {
+            scope: PauseScope::Connection {
+                connection_id: connection_id.into(),
+            },
+        });
+    }
+
+    assert_actions_not_paused(store, actions)
+}
+
+fn assert_actions_not_paused(store: &dyn Storage, actions: &[Action]) -> Result<()> {
+    for action in actions {
+        if PAUSED_ACTIONS.has(store, action.ty()) {
+            return Err(Error::Paused {
+                scope: PauseScope::Action {
+                    action: action.action_type(),
+                },
+            });
+        }
+
+        if let Action::Forward {
+            actions,
+            ..
+        } = action
+        {
+            assert_actions_not_paused(store, actions)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn pause_status(store: &dyn Storage) -> StdResult<PauseStatusResponse> {
+    Ok(PauseStatusResponse {
+        global:      GLOBAL_PAUSE.may_load(store)?.unwrap_or(false),
+        connections: PAUSED_CONNECTIONS
+            .keys(store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?,
+        actions:     PAUSED_ACTIONS
+            .keys(store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?,
+    })
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        from_slice,
+        testing::{mock_dependencies, mock_info},
+        Binary, Uint128,
+    };
+    use ics999::ActionType;
+
+    use super::*;
+
+    #[test]
+    fn pausing() {
+        let mut deps = mock_dependencies();
+
+        cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("larry")).unwrap();
+
+        let transfer = Action::Transfer {
+            denom:     "uatom".into(),
+            amount:    Uint128::new(12345),
+            recipient: None,
+        };
+        let query = Action::Query(Binary::default());
+
+        // non-owner cannot pause
+        {
+            let err = set_paused(deps.as_mut(), mock_info("jake", &[]), PauseScope::Global {}, true)
+                .unwrap_err();
+            assert!(matches!(err, Error::Ownership(_)));
+        }
+
+        // action types are referred to by name, and unknown ones are rejected
+        {
+            let scope: PauseScope = from_slice(br#"{"action":{"action":"transfer"}}"#).unwrap();
+            assert_eq!(scope, PauseScope::Action {
+                action: ActionType::Transfer,
+            });
+
+            assert!(from_slice::<PauseScope>(br#"{"action":{"action":"teleport"}}"#).is_err());
+        }
+
+        // pause transfers only
+        {
+            let scope = PauseScope::Action {
+                action: ActionType::Transfer,
+            };
+            set_paused(deps.as_mut(), mock_info("larry", &[]), scope.clone(), true).unwrap();
+
+            let actions = [query.clone(), transfer.clone()];
+            let err = assert_not_paused(deps.as_ref().storage, "connection-0", &actions)
+                .unwrap_err();
+            assert_eq!(err, Error::Paused { scope: scope.clone() });
+
+            let actions = [query.clone()];
+            assert!(assert_not_paused(deps.as_ref().storage, "connection-0", &actions).is_ok());
+
+            // transfers nested in forwards are paused too
+            let actions = [Action::Forward {
+                connection_id: "connection-1".into(),
+                actions:       vec![Action::Forward {
+                    connection_id: "connection-2".into(),
+                    actions:       vec![transfer.clone()],
+                }],
+            }];
+            let err = assert_not_paused(deps.as_ref().storage, "connection-0", &actions)
+                .unwrap_err();
+            assert_eq!(err, Error::Paused { scope: scope.clone() });
+
+            set_paused(deps.as_mut(), mock_info("larry", &[]), scope, false).unwrap();
+        }
+
+        // pause a single connection
+        {
+            let scope = PauseScope::Connection {
+                connection_id: "connection-0".into(),
+            };
+            set_paused(deps.as_mut(), mock_info("larry", &[]), scope.clone(), true).unwrap();
+
+            let actions = [query.clone()];
+            let err = assert_not_paused(deps.as_ref().storage, "connection-0", &actions)
+                .unwrap_err();
+            assert_eq!(err, Error::Paused { scope });
+
+            assert!(assert_not_paused(deps.as_ref().storage, "connection-1", &actions).is_ok());
+        }
+
+        // pause everything
+        {
+            set_paused(deps.as_mut(), mock_info("larry", &[]), PauseScope::Global {}, true).unwrap();
+
+            let err = assert_not_paused(deps.as_ref().storage, "connection-1", &[query])
+                .unwrap_err();
+            assert_eq!(err, Error::Paused { scope: PauseScope::Global {} });
+        }
+
+        let status = pause_status(deps.as_ref().storage).unwrap();
+        assert_eq!(
+            status,
+            PauseStatusResponse {
+                global:      true,
+                connections: vec!["connection-0".into()],
+                actions:     vec![],
+            },
+        );
+    }
+}
@@ -1,11 +1,20 @@
 use {
     crate::{
-        msg::{AccountKey, AccountResponse, ActiveChannelResponse, Config, DenomHashResponse},
-        state::{ACCOUNTS, ACTIVE_CHANNELS, CONFIG, DENOM_TRACES},
+        msg::{
+            AccountKey, AccountResponse, ActiveChannelResponse, Config, DenomHashResponse,
+            FailedCallback, FailedForwardAck, PacketFee, PacketInfo, PacketKey, RateLimitKey,
+            RateLimitResponse,
+        },
+        state::{
+            ACCOUNTS, ACTIVE_CHANNELS, CONFIG, DENOM_CREATION_POOL, DENOM_TRACES, ESCROWS,
+            FAILED_CALLBACKS, FAILED_FORWARD_ACKS, FLOWS, PACKETS, PACKET_FEES, RATE_LIMITS,
+            VOUCHER_SUPPLIES,
+        },
+        timeout,
         transfer::TraceItem,
     },
-    cosmwasm_std::{Deps, IbcEndpoint, StdResult},
-    cw_paginate::paginate_map,
+    cosmwasm_std::{Coin, Deps, IbcEndpoint, Order, StdResult},
+    cw_paginate::{collect, paginate_map},
     cw_storage_plus::Bound,
     ics999::Trace,
 };
@@ -96,3 +105,138 @@ pub fn active_channels(
         })
     })
 }
+
+pub fn denom_creation_pool(deps: Deps) -> StdResult<Vec<Coin>> {
+    DENOM_CREATION_POOL
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(Coin {
+                denom,
+                amount,
+            })
+        })
+        .collect()
+}
+
+pub fn packet_fee(deps: Deps, channel_id: String, sequence: u64) -> StdResult<PacketFee> {
+    PACKET_FEES.load(deps.storage, (&channel_id, sequence))
+}
+
+pub fn rate_limit(
+    deps:          Deps,
+    connection_id: String,
+    denom:         String,
+) -> StdResult<RateLimitResponse> {
+    Ok(RateLimitResponse {
+        rate_limit: RATE_LIMITS.load(deps.storage, (&connection_id, &denom))?,
+        flow:       FLOWS.may_load(deps.storage, (&connection_id, &denom))?,
+        connection_id,
+        denom,
+    })
+}
+
+pub fn rate_limits(
+    deps:        Deps,
+    start_after: Option<RateLimitKey>,
+    limit:       Option<u32>,
+) -> StdResult<Vec<RateLimitResponse>> {
+    let start = start_after
+        .as_ref()
+        .map(|RateLimitKey { connection_id, denom }| {
+            Bound::exclusive((connection_id.as_str(), denom.as_str()))
+        });
+
+    paginate_map(&RATE_LIMITS, deps.storage, start, limit, |(connection_id, denom), rate_limit| {
+        Ok(RateLimitResponse {
+            flow: FLOWS.may_load(deps.storage, (&connection_id, &denom))?,
+            connection_id,
+            denom,
+            rate_limit,
+        })
+    })
+}
+
+pub fn packet(deps: Deps, channel_id: String, sequence: u64) -> StdResult<PacketInfo> {
+    PACKETS.load(deps.storage, (&channel_id, sequence))
+}
+
+pub fn pending_packets(
+    deps:        Deps,
+    controller:  String,
+    start_after: Option<PacketKey>,
+    limit:       Option<u32>,
+) -> StdResult<Vec<PacketInfo>> {
+    let start = start_after
+        .as_ref()
+        .map(|PacketKey { channel_id, sequence }| {
+            Bound::exclusive((channel_id.as_str(), *sequence))
+        });
+
+    let iter = PACKETS
+        .idx
+        .status
+        .prefix((controller, "pending".into()))
+        .range(deps.storage, start, None, Order::Ascending);
+
+    collect(Box::new(iter), limit, |_, packet| Ok(packet))
+}
+
+pub fn failed_callback(deps: Deps, channel_id: String, sequence: u64) -> StdResult<FailedCallback> {
+    FAILED_CALLBACKS.load(deps.storage, (&channel_id, sequence))
+}
+
+pub fn failed_callbacks(
+    deps:        Deps,
+    start_after: Option<PacketKey>,
+    limit:       Option<u32>,
+) -> StdResult<Vec<FailedCallback>> {
+    let start = start_after
+        .as_ref()
+        .map(|PacketKey { channel_id, sequence }| {
+            Bound::exclusive((channel_id.as_str(), *sequence))
+        });
+
+    paginate_map(&FAILED_CALLBACKS, deps.storage, start, limit, |_, callback| Ok(callback))
+}
+
+pub fn failed_forward_ack(
+    deps:       Deps,
+    channel_id: String,
+    sequence:   u64,
+) -> StdResult<FailedForwardAck> {
+    FAILED_FORWARD_ACKS.load(deps.storage, (&channel_id, sequence))
+}
+
+pub fn failed_forward_acks(
+    deps:        Deps,
+    start_after: Option<PacketKey>,
+    limit:       Option<u32>,
+) -> StdResult<Vec<FailedForwardAck>> {
+    let start = start_after
+        .as_ref()
+        .map(|PacketKey { channel_id, sequence }| {
+            Bound::exclusive((channel_id.as_str(), *sequence))
+        });
+
+    paginate_map(&FAILED_FORWARD_ACKS, deps.storage, start, limit, |_, ack| Ok(ack))
+}
+
+pub fn escrow_balance(deps: Deps, channel_id: String, denom: String) -> StdResult<Coin> {
+    Ok(Coin {
+        amount: ESCROWS.may_load(deps.storage, (&channel_id, &denom))?.unwrap_or_default(),
+        denom,
+    })
+}
+
+pub fn default_timeout(deps: Deps, connection_id: String) -> StdResult<u64> {
+    let cfg = CONFIG.load(deps.storage)?;
+    timeout::default_timeout_secs(deps.storage, &cfg, &connection_id)
+}
+
+pub fn voucher_supply(deps: Deps, denom: String) -> StdResult<Coin> {
+    Ok(Coin {
+        amount: VOUCHER_SUPPLIES.may_load(deps.storage, &denom)?.unwrap_or_default(),
+        denom,
+    })
+}
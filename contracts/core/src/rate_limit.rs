@@ -0,0 +1,257 @@
+use {
+    crate::{
+        error::{Error, Result},
+        msg::{Flow, Quota, RateLimit},
+        state::{FLOWS, RATE_LIMITS},
+        transfer::total_supply,
+        CustomMsg,
+    },
+    cosmwasm_std::{
+        BlockInfo, DepsMut, MessageInfo, QuerierWrapper, Response, StdResult, Storage, Uint128,
+    },
+};
+
+impl Quota {
+    /// The maximum amount allowed in a period, given the token's total supply at
+    /// the start of the period.
+    pub fn amount(&self, supply: Uint128) -> Uint128 {
+        match self {
+            Quota::Absolute(amount) => *amount,
+            Quota::SupplyRatio(ratio) => supply * *ratio,
+        }
+    }
+}
+
+pub fn set_rate_limit(
+    deps:          DepsMut,
+    info:          MessageInfo,
+    connection_id: String,
+    denom:         String,
+    rate_limit:    Option<RateLimit>,
+) -> Result<Response<CustomMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    match &rate_limit {
+        Some(rl) => {
+            if rl.period_secs == 0 {
+                return Err(Error::ZeroRatePeriod);
+            }
+
+            // the flow in the current period, if any, is kept, so that the
+            // counters can't be reset by updating the limit
+            RATE_LIMITS.save(deps.storage, (&connection_id, &denom), rl)?;
+        },
+        None => {
+            RATE_LIMITS.remove(deps.storage, (&connection_id, &denom));
+            FLOWS.remove(deps.storage, (&connection_id, &denom));
+        },
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "set_rate_limit")
+        .add_attribute("connection_id", connection_id)
+        .add_attribute("denom", denom)
+        .add_attribute("removed", rate_limit.is_none().to_string()))
+}
+
+/// Record tokens being released or minted on this chain upon receiving a
+/// packet. Error if the rate limit is exceeded.
+///
+/// There is no need to roll back the inflow if the packet fails, because in
+/// that case all state changes made while handling the packet are reverted.
+pub fn record_inflow(
+    store:         &mut dyn Storage,
+    querier:       &QuerierWrapper,
+    block:         &BlockInfo,
+    connection_id: &str,
+    denom:         &str,
+    amount:        Uint128,
+) -> Result<()> {
+    record(store, querier, block, connection_id, denom, amount, Direction::Inflow)
+}
+
+/// Record tokens being escrowed or burned on this chain upon sending a packet.
+/// Error if the rate limit is exceeded.
+pub fn record_outflow(
+    store:         &mut dyn Storage,
+    querier:       &QuerierWrapper,
+    block:         &BlockInfo,
+    connection_id: &str,
+    denom:         &str,
+    amount:        Uint128,
+) -> Result<()> {
+    record(store, querier, block, connection_id, denom, amount, Direction::Outflow)
+}
+
+/// Roll back the outflow of tokens that are refunded because the packet timed
+/// out or failed.
+///
+/// If the period in which the tokens were sent has already ended, the flow has
+/// since been reset, so there is nothing to roll back.
+pub fn undo_outflow(
+    store:         &mut dyn Storage,
+    block:         &BlockInfo,
+    connection_id: &str,
+    denom:         &str,
+    amount:        Uint128,
+) -> StdResult<()> {
+    let Some(mut flow) = FLOWS.may_load(store, (connection_id, denom))? else {
+        return Ok(());
+    };
+
+    if block.time >= flow.period_end {
+        return Ok(());
+    }
+
+    flow.outflow = flow.outflow.saturating_sub(amount);
+
+    FLOWS.save(store, (connection_id, denom), &flow)
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Inflow,
+    Outflow,
+}
+
+fn record(
+    store:         &mut dyn Storage,
+    querier:       &QuerierWrapper,
+    block:         &BlockInfo,
+    connection_id: &str,
+    denom:         &str,
+    amount:        Uint128,
+    direction:     Direction,
+) -> Result<()> {
+    // tokens without a rate limit are not tracked
+    let Some(rl) = RATE_LIMITS.may_load(store, (connection_id, denom))? else {
+        return Ok(());
+    };
+
+    // start a new period if there isn't one or the current one has ended
+    let mut flow = match FLOWS.may_load(store, (connection_id, denom))? {
+        Some(flow) if block.time < flow.period_end => flow,
+        _ => Flow {
+            period_end: block.time.plus_seconds(rl.period_secs),
+            supply:     total_supply(querier, denom)?,
+            inflow:     Uint128::zero(),
+            outflow:    Uint128::zero(),
+        },
+    };
+
+    let (total, max) = match direction {
+        Direction::Inflow => (&mut flow.inflow, &rl.max_inflow),
+        Direction::Outflow => (&mut flow.outflow, &rl.max_outflow),
+    };
+
+    *total = total.checked_add(amount)?;
+
+    if let Some(quota) = max {
+        let quota = quota.amount(flow.supply);
+
+        if *total > quota {
+            return Err(Error::RateLimitExceeded {
+                connection_id: connection_id.into(),
+                denom:         denom.into(),
+                direction:     direction.to_string(),
+                quota,
+                attempted:     *total,
+            });
+        }
+    }
+
+    Ok(FLOWS.save(store, (connection_id, denom), &flow)?)
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::Inflow => write!(f, "inflow"),
+            Direction::Outflow => write!(f, "outflow"),
+        }
+    }
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coins,
+        testing::{mock_dependencies_with_balances, mock_env, mock_info},
+        Decimal,
+    };
+
+    use super::*;
+
+    const CONNECTION_ID: &str = "connection-0";
+
+    #[test]
+    fn rate_limiting() {
+        let mut deps = mock_dependencies_with_balances(&[("jake", &coins(1000, "uatom"))]);
+        let mut env = mock_env();
+
+        cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("larry")).unwrap();
+
+        set_rate_limit(
+            deps.as_mut(),
+            mock_info("larry", &[]),
+            CONNECTION_ID.into(),
+            "uatom".into(),
+            Some(RateLimit {
+                period_secs: 60,
+                max_inflow:  Some(Quota::SupplyRatio(Decimal::percent(10))),
+                max_outflow: Some(Quota::Absolute(Uint128::new(50))),
+            }),
+        )
+        .unwrap();
+
+        let querier = QuerierWrapper::new(&deps.querier);
+        let store = &mut deps.storage;
+
+        // inflow quota is 10% of the supply of 1000
+        record_inflow(store, &querier, &env.block, CONNECTION_ID, "uatom", 80u128.into()).unwrap();
+        let err = record_inflow(store, &querier, &env.block, CONNECTION_ID, "uatom", 21u128.into())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            Error::RateLimitExceeded {
+                connection_id: CONNECTION_ID.into(),
+                denom:         "uatom".into(),
+                direction:     "inflow".into(),
+                quota:         Uint128::new(100),
+                attempted:     Uint128::new(101),
+            },
+        );
+
+        // inflow and outflow are tracked separately
+        record_outflow(store, &querier, &env.block, CONNECTION_ID, "uatom", 50u128.into()).unwrap();
+        record_outflow(store, &querier, &env.block, CONNECTION_ID, "uatom", 1u128.into())
+            .unwrap_err();
+
+        // refunded outflow no longer counts towards the quota
+        undo_outflow(store, &env.block, CONNECTION_ID, "uatom", 20u128.into()).unwrap();
+        record_outflow(store, &querier, &env.block, CONNECTION_ID, "uatom", 20u128.into()).unwrap();
+
+        // other denoms and connections are not limited
+        record_outflow(store, &querier, &env.block, CONNECTION_ID, "uosmo", 1000u128.into())
+            .unwrap();
+        record_outflow(store, &querier, &env.block, "connection-1", "uatom", 1000u128.into())
+            .unwrap();
+
+        // the flow resets once the period has ended
+        env.block.time = env.block.time.plus_seconds(60);
+        record_outflow(store, &querier, &env.block, CONNECTION_ID, "uatom", 50u128.into()).unwrap();
+
+        let flow = FLOWS.load(store, (CONNECTION_ID, "uatom")).unwrap();
+        assert_eq!(
+            flow,
+            Flow {
+                period_end: env.block.time.plus_seconds(60),
+                supply:     Uint128::new(1000),
+                inflow:     Uint128::zero(),
+                outflow:    Uint128::new(50),
+            },
+        );
+    }
+}
@@ -0,0 +1,403 @@
+use {
+    crate::{
+        error::{Error, Result},
+        state::{
+            ACCOUNTS, ACTIVE_CHANNELS, CLOSED_CHANNELS, COMMITMENTS, DENOM_ALIASES, DENOM_TRACES,
+            ESCROWS, RETIRED_CHANNELS,
+        },
+        transfer::{add_escrow, derive_voucher_denom, TraceItem},
+        CustomMsg,
+    },
+    cosmwasm_std::{Addr, Coin, DepsMut, Env, MessageInfo, Order, Response, StdResult, Uint128},
+    cw_paginate::{paginate_map, paginate_map_prefix},
+    cw_storage_plus::Bound,
+};
+
+pub fn retire_channel(
+    deps:          DepsMut,
+    info:          MessageInfo,
+    connection_id: String,
+) -> Result<Response<CustomMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let endpoint = ACTIVE_CHANNELS.load(deps.storage, &connection_id)?;
+
+    // only a channel that has actually been closed can be retired
+    if CLOSED_CHANNELS.may_load(deps.storage, &connection_id)? != Some(endpoint.clone()) {
+        return Err(Error::ChannelNotClosed {
+            connection_id,
+        });
+    }
+
+    ACTIVE_CHANNELS.remove(deps.storage, &connection_id);
+    CLOSED_CHANNELS.remove(deps.storage, &connection_id);
+    RETIRED_CHANNELS.save(deps.storage, &connection_id, &endpoint)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "retire_channel")
+        .add_attribute("connection_id", connection_id)
+        .add_attribute("port_id", endpoint.port_id)
+        .add_attribute("channel_id", endpoint.channel_id))
+}
+
+pub fn remap_accounts(
+    deps:          DepsMut,
+    info:          MessageInfo,
+    connection_id: String,
+    start_after:   Option<String>,
+    limit:         Option<u32>,
+) -> Result<Response<CustomMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let old = RETIRED_CHANNELS.load(deps.storage, &connection_id)?;
+    let new = ACTIVE_CHANNELS.load(deps.storage, &connection_id)?;
+
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let accounts = paginate_map_prefix(
+        &ACCOUNTS,
+        deps.storage,
+        (&old.port_id, &old.channel_id),
+        start,
+        limit,
+        |controller, address| -> StdResult<(String, Addr)> { Ok((controller, address)) },
+    )?;
+
+    let mut res = Response::new()
+        .add_attribute("method", "remap_accounts")
+        .add_attribute("connection_id", connection_id);
+
+    let mut count = 0;
+
+    for (controller, address) in &accounts {
+        // if the controller has already registered a new account via the new
+        // channel, we can't move the old one without overwriting it. leave it
+        // where it is.
+        if ACCOUNTS.has(deps.storage, (&new.port_id, &new.channel_id, controller)) {
+            res = res.add_attribute("skipped", controller);
+            continue;
+        }
+
+        ACCOUNTS.remove(deps.storage, (&old.port_id, &old.channel_id, controller));
+        ACCOUNTS.save(deps.storage, (&new.port_id, &new.channel_id, controller), address)?;
+
+        count += 1;
+    }
+
+    res = res.add_attribute("remapped", count.to_string());
+
+    if let Some((controller, _)) = accounts.last() {
+        res = res.add_attribute("last_controller", controller);
+    }
+
+    Ok(res)
+}
+
+pub fn remap_vouchers(
+    deps:          DepsMut,
+    env:           Env,
+    info:          MessageInfo,
+    connection_id: String,
+    start_after:   Option<String>,
+    limit:         Option<u32>,
+) -> Result<Response<CustomMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let old = RETIRED_CHANNELS.load(deps.storage, &connection_id)?;
+    let new = ACTIVE_CHANNELS.load(deps.storage, &connection_id)?;
+
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let mut traces = paginate_map(
+        &DENOM_TRACES,
+        deps.storage,
+        start,
+        limit,
+        |denom, trace| -> StdResult<(String, TraceItem)> { Ok((denom, trace)) },
+    )?;
+
+    let mut res = Response::new()
+        .add_attribute("method", "remap_vouchers")
+        .add_attribute("connection_id", connection_id);
+
+    let mut count = 0;
+
+    for (denom, trace) in &mut traces {
+        if !trace.path.contains(&old) {
+            continue;
+        }
+
+        for step in trace.path.iter_mut().filter(|step| **step == old) {
+            *step = new.clone();
+        }
+
+        // the voucher denom is derived from the trace hash, which has changed.
+        // we can't rename tokens that already exist, so instead we remember
+        // that the new denom is an alias of the existing one.
+        let alias = derive_voucher_denom(deps.as_ref(), env.contract.address.as_str(), trace)?;
+
+        DENOM_TRACES.save(deps.storage, denom, trace)?;
+        DENOM_ALIASES.save(deps.storage, &alias, denom)?;
+
+        count += 1;
+    }
+
+    res = res.add_attribute("remapped", count.to_string());
+
+    if let Some((denom, _)) = traces.last() {
+        res = res.add_attribute("last_denom", denom);
+    }
+
+    Ok(res)
+}
+
+pub fn remap_escrows(
+    deps:          DepsMut,
+    info:          MessageInfo,
+    connection_id: String,
+    start_after:   Option<String>,
+    limit:         Option<u32>,
+) -> Result<Response<CustomMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    let old = RETIRED_CHANNELS.load(deps.storage, &connection_id)?;
+    let new = ACTIVE_CHANNELS.load(deps.storage, &connection_id)?;
+
+    // refunds of packets in flight on the retired channel release tokens from
+    // its escrow, so it can't be moved until they are complete
+    if COMMITMENTS
+        .prefix(&old.channel_id)
+        .keys_raw(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some()
+    {
+        return Err(Error::PacketsInFlight {
+            channel_id: old.channel_id,
+        });
+    }
+
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let escrows = paginate_map_prefix(
+        &ESCROWS,
+        deps.storage,
+        &old.channel_id,
+        start,
+        limit,
+        |denom, amount| -> StdResult<(String, Uint128)> { Ok((denom, amount)) },
+    )?;
+
+    let mut res = Response::new()
+        .add_attribute("method", "remap_escrows")
+        .add_attribute("connection_id", connection_id);
+
+    for (denom, amount) in &escrows {
+        ESCROWS.remove(deps.storage, (&old.channel_id, denom));
+        add_escrow(deps.storage, &new.channel_id, &Coin::new(amount.u128(), denom))?;
+    }
+
+    res = res.add_attribute("remapped", escrows.len().to_string());
+
+    if let Some((denom, _)) = escrows.last() {
+        res = res.add_attribute("last_denom", denom);
+    }
+
+    Ok(res)
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR},
+        attr, IbcChannel, IbcChannelCloseMsg, IbcEndpoint,
+    };
+
+    use super::*;
+    use crate::{
+        controller::Commitment,
+        handshake::{close, open_connect, open_init},
+        msg::{Config, TokenFactoryFlavor, VoucherBackend},
+        state::CONFIG,
+        transfer::voucher_denom,
+    };
+
+    const CONNECTION_ID: &str = "connection-0";
+
+    fn mock_ibc_channel(channel_id: &str) -> IbcChannel {
+        IbcChannel::new(
+            IbcEndpoint {
+                port_id:    format!("wasm.{MOCK_CONTRACT_ADDR}"),
+                channel_id: channel_id.into(),
+            },
+            IbcEndpoint {
+                port_id:    "wasm.counterparty".into(),
+                channel_id: channel_id.into(),
+            },
+            ics999::ORDER,
+            ics999::VERSION,
+            CONNECTION_ID,
+        )
+    }
+
+    #[test]
+    fn replacing_channel() {
+        let mut deps = mock_dependencies();
+
+        cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("larry")).unwrap();
+        CONFIG
+            .save(deps.as_mut().storage, &Config {
+                default_account_code_id:    1,
+                default_timeout_secs:       300,
+                max_timeout_secs:           86_400,
+                voucher_backend:            VoucherBackend::TokenFactory {
+                    flavor: TokenFactoryFlavor::Osmosis,
+                },
+                default_callback_gas_limit: 1_000_000,
+                max_callback_gas_limit:     5_000_000,
+            })
+            .unwrap();
+
+        let old_channel = mock_ibc_channel("channel-0");
+        let new_channel = mock_ibc_channel("channel-1");
+        let old = old_channel.endpoint.clone();
+        let new = new_channel.endpoint.clone();
+
+        open_connect(deps.as_mut(), &old_channel, Some(ics999::VERSION)).unwrap();
+
+        // an account and a voucher created via the old channel
+        ACCOUNTS
+            .save(
+                deps.as_mut().storage,
+                (&old.port_id, &old.channel_id, "jake"),
+                &Addr::unchecked("ica"),
+            )
+            .unwrap();
+
+        let old_trace = TraceItem {
+            base_denom: "uatom".into(),
+            path:       vec![old.clone()],
+        };
+        let denom = voucher_denom(deps.as_ref(), MOCK_CONTRACT_ADDR, &old_trace).unwrap();
+        DENOM_TRACES.save(deps.as_mut().storage, &denom, &old_trace).unwrap();
+
+        // tokens escrowed for transfers sent via the old channel
+        ESCROWS
+            .save(deps.as_mut().storage, (&old.channel_id, "uosmo"), &Uint128::new(100))
+            .unwrap();
+
+        // cannot retire a channel that is not closed
+        {
+            let err = retire_channel(deps.as_mut(), mock_info("larry", &[]), CONNECTION_ID.into())
+                .unwrap_err();
+            assert!(matches!(err, Error::ChannelNotClosed { .. }));
+        }
+
+        close(deps.as_mut(), IbcChannelCloseMsg::CloseConfirm {
+            channel: old_channel,
+        })
+        .unwrap();
+
+        // non-owner cannot retire the channel
+        {
+            let err = retire_channel(deps.as_mut(), mock_info("jake", &[]), CONNECTION_ID.into())
+                .unwrap_err();
+            assert!(matches!(err, Error::Ownership(_)));
+        }
+
+        retire_channel(deps.as_mut(), mock_info("larry", &[]), CONNECTION_ID.into()).unwrap();
+
+        // a new channel can now be opened on the same connection
+        open_init(deps.as_mut(), new_channel.clone()).unwrap();
+        open_connect(deps.as_mut(), &new_channel, Some(ics999::VERSION)).unwrap();
+
+        // some tokens have already been escrowed for the new channel
+        ESCROWS
+            .save(deps.as_mut().storage, (&new.channel_id, "uosmo"), &Uint128::new(20))
+            .unwrap();
+
+        // pumpkin has accounts via both channels, so the old one is skipped
+        for endpoint in [&old, &new] {
+            ACCOUNTS
+                .save(
+                    deps.as_mut().storage,
+                    (&endpoint.port_id, &endpoint.channel_id, "pumpkin"),
+                    &Addr::unchecked(format!("ica-{}", endpoint.channel_id)),
+                )
+                .unwrap();
+        }
+
+        let res = remap_accounts(
+            deps.as_mut(),
+            mock_info("larry", &[]),
+            CONNECTION_ID.into(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("skipped", "pumpkin")));
+        assert!(res.attributes.contains(&attr("remapped", "1")));
+
+        remap_vouchers(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("larry", &[]),
+            CONNECTION_ID.into(),
+            None,
+            None,
+        )
+        .unwrap();
+
+        // escrows can't be moved while packets that may be refunded from them
+        // are in flight
+        COMMITMENTS
+            .save(deps.as_mut().storage, (&old.channel_id, 7), &vec![Commitment::Escrowed(
+                Coin::new(20, "uosmo"),
+            )])
+            .unwrap();
+        let err = remap_escrows(
+            deps.as_mut(),
+            mock_info("larry", &[]),
+            CONNECTION_ID.into(),
+            None,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err, Error::PacketsInFlight {
+            channel_id: old.channel_id.clone(),
+        });
+
+        COMMITMENTS.remove(deps.as_mut().storage, (&old.channel_id, 7));
+        let res = remap_escrows(
+            deps.as_mut(),
+            mock_info("larry", &[]),
+            CONNECTION_ID.into(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("last_denom", "uosmo")));
+
+        // the account is now keyed by the new channel
+        let account = ACCOUNTS
+            .may_load(deps.as_ref().storage, (&new.port_id, &new.channel_id, "jake"))
+            .unwrap();
+        assert_eq!(account, Some(Addr::unchecked("ica")));
+        assert!(!ACCOUNTS.has(deps.as_ref().storage, (&old.port_id, &old.channel_id, "jake")));
+
+        // the escrow has been added to the new channel's
+        let escrowed = ESCROWS.load(deps.as_ref().storage, (&new.channel_id, "uosmo")).unwrap();
+        assert_eq!(escrowed, Uint128::new(120));
+        assert!(!ESCROWS.has(deps.as_ref().storage, (&old.channel_id, "uosmo")));
+
+        // the voucher's trace now goes through the new channel, and the denom
+        // derived from the new trace resolves to the existing voucher
+        let new_trace = TraceItem {
+            base_denom: "uatom".into(),
+            path:       vec![new],
+        };
+        assert_eq!(DENOM_TRACES.load(deps.as_ref().storage, &denom).unwrap(), new_trace);
+        assert_eq!(
+            voucher_denom(deps.as_ref(), MOCK_CONTRACT_ADDR, &new_trace).unwrap(),
+            denom,
+        );
+    }
+}
@@ -1,7 +1,16 @@
 use {
-    crate::{msg::Config, transfer::TraceItem},
-    cosmwasm_std::{Addr, IbcEndpoint},
-    cw_storage_plus::{Item, Map},
+    crate::{
+        controller::{Commitment, PacketRecords, PendingCallback},
+        forward::{ForwardOrigin, PendingAck},
+        msg::{
+            CallbackOptions, Config, FailedCallback, FailedForwardAck, Flow, PacketFee, PacketInfo,
+            PacketStatus, RateLimit,
+        },
+        transfer::TraceItem,
+    },
+    cosmwasm_std::{Addr, Empty, IbcEndpoint, Uint128},
+    ics999::ForwardAck,
+    cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex},
 };
 
 pub const CONFIG: Item<Config> = Item::new("cfg");
@@ -14,3 +23,116 @@ pub const DENOM_TRACES: Map<&str, TraceItem> = Map::new("dt");
 
 // connection_id => ibc_endpoint
 pub const ACTIVE_CHANNELS: Map<&str, IbcEndpoint> = Map::new("actchan");
+
+// channel_id => connection_id
+pub const CHANNEL_CONNECTIONS: Map<&str, String> = Map::new("chanconn");
+
+// the records of the packet being sent, saved until its sequence is known in
+// the reply
+pub const SENDING_PACKET: Item<PacketRecords> = Item::new("sendpkt");
+
+// (channel_id, sequence) => relayer fees escrowed for the packet
+pub const PACKET_FEES: Map<(&str, u64), PacketFee> = Map::new("fee");
+
+// (channel_id, denom) => amount of tokens escrowed for transfers sent through
+// the channel, which is the most that can be released through it
+pub const ESCROWS: Map<(&str, &str), Uint128> = Map::new("escrow");
+
+// voucher denom => amount of the voucher that has been minted and not burned
+pub const VOUCHER_SUPPLIES: Map<&str, Uint128> = Map::new("vsupply");
+
+// (channel_id, sequence) => what was done with each token transferred by the
+// packet, to be undone if the transfer is refunded
+pub const COMMITMENTS: Map<(&str, u64), Vec<Commitment>> = Map::new("commit");
+
+// (channel_id, sequence) => gas limit of the callback upon the packet's
+// completion, if the user requested one other than the default
+pub const CALLBACK_GAS_LIMITS: Map<(&str, u64), u64> = Map::new("cbgas");
+
+// (channel_id, sequence) => how the controller is to be called back upon the
+// packet's completion, if the user provided any options
+pub const CALLBACK_OPTIONS: Map<(&str, u64), CallbackOptions> = Map::new("cbopt");
+
+// the callback being executed, saved until its result is known in the reply
+pub const PENDING_CALLBACK: Item<PendingCallback> = Item::new("pendcb");
+
+// (channel_id, sequence) => callback that failed, to be retried by anyone
+pub const FAILED_CALLBACKS: Map<(&str, u64), FailedCallback> = Map::new("failcb");
+
+// (channel_id, sequence) => packet relaying the outcome of a forwarded packet
+// that was rejected by the counterparty, to be sent again by the admin
+pub const FAILED_FORWARD_ACKS: Map<(&str, u64), FailedForwardAck> = Map::new("failfwdack");
+
+// connection_id => ibc_endpoint of a channel that has been closed by the
+// counterparty, but not yet retired by the admin
+pub const CLOSED_CHANNELS: Map<&str, IbcEndpoint> = Map::new("closedchan");
+
+// connection_id => ibc_endpoint of a closed channel that has been retired by the
+// admin, whose accounts and vouchers are to be remapped to the new active channel
+pub const RETIRED_CHANNELS: Map<&str, IbcEndpoint> = Map::new("retchan");
+
+// denom => amount available for paying tokenfactory denom creation fees
+pub const DENOM_CREATION_POOL: Map<&str, Uint128> = Map::new("dcpool");
+
+// denom derived from a remapped trace => the voucher denom originally created
+// for the trace
+pub const DENOM_ALIASES: Map<&str, String> = Map::new("alias");
+
+// whether all connections and actions are paused
+pub const GLOBAL_PAUSE: Item<bool> = Item::new("gpause");
+
+// connection_id => ()
+pub const PAUSED_CONNECTIONS: Map<&str, Empty> = Map::new("pausedconn");
+
+// action_type => ()
+pub const PAUSED_ACTIONS: Map<&str, Empty> = Map::new("pausedact");
+
+// (connection_id, denom) => rate_limit
+pub const RATE_LIMITS: Map<(&str, &str), RateLimit> = Map::new("rl");
+
+// (connection_id, denom) => flow in the current rate limit period
+pub const FLOWS: Map<(&str, &str), Flow> = Map::new("flow");
+
+// connection_id => default timeout (in seconds) of packets sent on the
+// connection, overriding the one in the config
+pub const DEFAULT_TIMEOUTS: Map<&str, u64> = Map::new("dto");
+
+// (channel_id, sequence) of a packet sent on behalf of a counterparty =>
+// where the packet containing the forward action came from
+pub const FORWARDS: Map<(&str, u64), ForwardOrigin> = Map::new("fwd");
+
+// (channel_id, sequence) => a packet that has been acknowledged, but is waiting
+// for the outcomes of the packets it forwarded
+pub const PENDING_ACKS: Map<(&str, u64), PendingAck> = Map::new("pendack");
+
+// (channel_id, sequence) => outcomes of forwarded packets that were relayed back
+// before the packet was acknowledged
+pub const EARLY_FORWARD_ACKS: Map<(&str, u64), Vec<ForwardAck>> = Map::new("earlyfwdack");
+
+// (channel_id, sequence) => a packet sent by this contract, whether pending or
+// complete, indexed by its controller and whether it's pending, so that the
+// pending packets of a controller can be listed without going over its history
+pub const PACKETS: IndexedMap<(&str, u64), PacketInfo, PacketIndexes> =
+    IndexedMap::new("pkt", PacketIndexes {
+        status: MultiIndex::new(packet_status, "pkt", "pkt__status"),
+    });
+
+pub struct PacketIndexes<'a> {
+    pub status: MultiIndex<'a, (String, String), PacketInfo, (&'a str, u64)>,
+}
+
+impl<'a> IndexList<PacketInfo> for PacketIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<PacketInfo>> + '_> {
+        let v: Vec<&dyn Index<PacketInfo>> = vec![&self.status];
+        Box::new(v.into_iter())
+    }
+}
+
+fn packet_status(_: &[u8], packet: &PacketInfo) -> (String, String) {
+    let status = match packet.status {
+        PacketStatus::Pending {} => "pending",
+        PacketStatus::Complete { .. } => "complete",
+    };
+
+    (packet.controller.clone(), status.into())
+}
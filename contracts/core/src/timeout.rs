@@ -0,0 +1,263 @@
+use {
+    crate::{
+        error::{Error, Result},
+        msg::{Config, Timeout},
+        state::{CONFIG, DEFAULT_TIMEOUTS},
+        CustomMsg,
+    },
+    cosmwasm_std::{BlockInfo, DepsMut, IbcTimeout, MessageInfo, Response, StdResult, Storage},
+};
+
+pub fn set_default_timeout(
+    deps:          DepsMut,
+    info:          MessageInfo,
+    connection_id: String,
+    timeout_secs:  Option<u64>,
+) -> Result<Response<CustomMsg>> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    match timeout_secs {
+        Some(secs) => {
+            let cfg = CONFIG.load(deps.storage)?;
+            assert_timeout_secs(&cfg, secs)?;
+
+            DEFAULT_TIMEOUTS.save(deps.storage, &connection_id, &secs)?;
+        },
+        None => {
+            DEFAULT_TIMEOUTS.remove(deps.storage, &connection_id);
+        },
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "set_default_timeout")
+        .add_attribute("connection_id", connection_id)
+        .add_attribute("removed", timeout_secs.is_none().to_string()))
+}
+
+/// A relative timeout requested by a user, or a default one set by the admin,
+/// must not exceed the maximum.
+pub(crate) fn assert_timeout_secs(cfg: &Config, secs: u64) -> Result<()> {
+    if secs > cfg.max_timeout_secs {
+        return Err(Error::TimeoutTooLong {
+            secs,
+            max: cfg.max_timeout_secs,
+        });
+    }
+
+    Ok(())
+}
+
+/// The default timeout (in seconds) of packets sent on the connection: the
+/// connection's own if the admin has set one, or otherwise the one in the
+/// config. Either is capped at the maximum, in case it has been lowered since.
+pub fn default_timeout_secs(
+    store:         &dyn Storage,
+    cfg:           &Config,
+    connection_id: &str,
+) -> StdResult<u64> {
+    let secs = DEFAULT_TIMEOUTS
+        .may_load(store, connection_id)?
+        .unwrap_or(cfg.default_timeout_secs);
+
+    Ok(secs.min(cfg.max_timeout_secs))
+}
+
+/// Turn the timeout requested for a packet into the one it is sent with.
+///
+/// A timeout with only a block height gets the latest timestamp allowed added,
+/// so that the packet can't stay in flight for longer than the maximum, even
+/// if the counterparty chain halts.
+pub fn resolve_timeout(
+    store:         &dyn Storage,
+    block:         &BlockInfo,
+    cfg:           &Config,
+    connection_id: &str,
+    timeout:       Option<Timeout>,
+) -> Result<IbcTimeout> {
+    let latest = block.time.plus_seconds(cfg.max_timeout_secs);
+
+    match timeout {
+        None => {
+            let secs = default_timeout_secs(store, cfg, connection_id)?;
+            Ok(IbcTimeout::with_timestamp(block.time.plus_seconds(secs)))
+        },
+        Some(Timeout::Relative {
+            secs,
+        }) => {
+            assert_timeout_secs(cfg, secs)?;
+            Ok(IbcTimeout::with_timestamp(block.time.plus_seconds(secs)))
+        },
+        Some(Timeout::Absolute(timeout)) => match (timeout.block(), timeout.timestamp()) {
+            (_, Some(timestamp)) if timestamp > latest => Err(Error::TimeoutTooLate {
+                timestamp,
+                latest,
+            }),
+            (Some(block), None) => Ok(IbcTimeout::with_both(block, latest)),
+            _ => Ok(timeout),
+        },
+    }
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        from_slice,
+        testing::{mock_dependencies, mock_env, mock_info},
+        IbcTimeoutBlock, Timestamp,
+    };
+
+    use super::*;
+    use crate::msg::{TokenFactoryFlavor, VoucherBackend};
+
+    #[test]
+    fn deserializing_timeout() {
+        let timeout: Timeout = from_slice(br#"{"secs":300}"#).unwrap();
+        assert_eq!(timeout, Timeout::Relative {
+            secs: 300,
+        });
+
+        // a raw `IbcTimeout`, as accepted before relative timeouts were added
+        let timeout: Timeout =
+            from_slice(br#"{"block":{"revision":1,"height":12345},"timestamp":null}"#).unwrap();
+        assert_eq!(
+            timeout,
+            Timeout::Absolute(IbcTimeout::with_block(IbcTimeoutBlock {
+                revision: 1,
+                height:   12345,
+            })),
+        );
+
+        let timeout: Timeout = from_slice(br#"{"timestamp":"1000000000"}"#).unwrap();
+        let timestamp = Timestamp::from_seconds(1);
+        assert_eq!(timeout, Timeout::Absolute(IbcTimeout::with_timestamp(timestamp)));
+    }
+
+    #[test]
+    fn resolving_timeout() {
+        let mut deps = mock_dependencies();
+        let block = mock_env().block;
+
+        cw_ownable::initialize_owner(&mut deps.storage, &deps.api, Some("larry")).unwrap();
+
+        let mut cfg = Config {
+            default_account_code_id:    1,
+            default_timeout_secs:       300,
+            max_timeout_secs:           3600,
+            voucher_backend:            VoucherBackend::TokenFactory {
+                flavor: TokenFactoryFlavor::Osmosis,
+            },
+            default_callback_gas_limit: 1_000_000,
+            max_callback_gas_limit:     5_000_000,
+        };
+        CONFIG.save(deps.as_mut().storage, &cfg).unwrap();
+
+        let resolve = |store: &dyn Storage, cfg: &Config, timeout: Option<Timeout>| {
+            resolve_timeout(store, &block, cfg, "connection-0", timeout)
+        };
+        let after = |secs: u64| IbcTimeout::with_timestamp(block.time.plus_seconds(secs));
+
+        // without a timeout, the default in the config is used
+        {
+            let timeout = resolve(deps.as_ref().storage, &cfg, None).unwrap();
+            assert_eq!(timeout, after(300));
+        }
+
+        // relative timeouts are counted from the current block time
+        {
+            let timeout =
+                resolve(deps.as_ref().storage, &cfg, Some(Timeout::Relative { secs: 1800 }))
+                    .unwrap();
+            assert_eq!(timeout, after(1800));
+
+            let err = resolve(deps.as_ref().storage, &cfg, Some(Timeout::Relative { secs: 3601 }))
+                .unwrap_err();
+            assert_eq!(err, Error::TimeoutTooLong {
+                secs: 3601,
+                max:  3600,
+            });
+        }
+
+        // absolute timestamps can't be later than the maximum allows
+        {
+            let timeout = after(3600);
+            let resolved =
+                resolve(deps.as_ref().storage, &cfg, Some(Timeout::Absolute(timeout.clone())))
+                    .unwrap();
+            assert_eq!(resolved, timeout);
+
+            let err = resolve(deps.as_ref().storage, &cfg, Some(Timeout::Absolute(after(3601))))
+                .unwrap_err();
+            assert_eq!(err, Error::TimeoutTooLate {
+                timestamp: block.time.plus_seconds(3601),
+                latest:    block.time.plus_seconds(3600),
+            });
+        }
+
+        // a block height timeout gets the latest timestamp allowed
+        {
+            let height = IbcTimeoutBlock {
+                revision: 1,
+                height:   12345,
+            };
+            let timeout = resolve(
+                deps.as_ref().storage,
+                &cfg,
+                Some(Timeout::Absolute(IbcTimeout::with_block(height))),
+            )
+            .unwrap();
+            assert_eq!(timeout, IbcTimeout::with_both(height, block.time.plus_seconds(3600)));
+        }
+
+        // only the owner can set a connection's default, which can't exceed the
+        // maximum
+        {
+            let err = set_default_timeout(
+                deps.as_mut(),
+                mock_info("jake", &[]),
+                "connection-0".into(),
+                Some(600),
+            )
+            .unwrap_err();
+            assert!(matches!(err, Error::Ownership(_)));
+
+            let err = set_default_timeout(
+                deps.as_mut(),
+                mock_info("larry", &[]),
+                "connection-0".into(),
+                Some(7200),
+            )
+            .unwrap_err();
+            assert!(matches!(err, Error::TimeoutTooLong { .. }));
+        }
+
+        // the connection's default overrides the one in the config
+        set_default_timeout(
+            deps.as_mut(),
+            mock_info("larry", &[]),
+            "connection-0".into(),
+            Some(600),
+        )
+        .unwrap();
+        {
+            let timeout = resolve(deps.as_ref().storage, &cfg, None).unwrap();
+            assert_eq!(timeout, after(600));
+        }
+
+        // defaults are capped if the maximum is lowered
+        cfg.max_timeout_secs = 450;
+        {
+            let timeout = resolve(deps.as_ref().storage, &cfg, None).unwrap();
+            assert_eq!(timeout, after(450));
+        }
+
+        // removing the connection's default falls back to the config's
+        set_default_timeout(deps.as_mut(), mock_info("larry", &[]), "connection-0".into(), None)
+            .unwrap();
+        {
+            let timeout = resolve(deps.as_ref().storage, &cfg, None).unwrap();
+            assert_eq!(timeout, after(300));
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use {
+    super::{ParamsResponse, TokenBackend},
+    cosmwasm_schema::{cw_serde, serde::Deserialize},
+    cosmwasm_std::{
+        Coin, CosmosMsg, CustomMsg, CustomQuery, QuerierWrapper, QueryRequest, StdResult, Uint128,
+    },
+};
+
+/// Tokenfactory messages, in the shape used by the token-bindings family of
+/// chain bindings.
+#[cw_serde]
+pub enum TokenFactoryMsg {
+    CreateDenom {
+        subdenom: String,
+    },
+    MintTokens {
+        denom:           String,
+        amount:          Uint128,
+        mint_to_address: String,
+    },
+    BurnTokens {
+        denom:             String,
+        amount:            Uint128,
+        burn_from_address: String,
+    },
+}
+
+impl CustomMsg for TokenFactoryMsg {}
+
+/// Tokenfactory queries, in the shape used by the token-bindings family of
+/// chain bindings.
+#[cw_serde]
+pub enum TokenFactoryQuery {
+    Params {},
+}
+
+impl CustomQuery for TokenFactoryQuery {}
+
+/// Tokenfactory accessed through `CosmosMsg::Custom` chain bindings instead of
+/// stargate messages.
+///
+/// Can only be selected if the contract is built with the `bindings` feature,
+/// which makes `TokenFactoryMsg` the custom message type of its responses.
+pub struct Bindings;
+
+impl TokenBackend<TokenFactoryMsg> for Bindings {
+    fn create_denom(&self, _sender: &str, subdenom: &str) -> StdResult<CosmosMsg<TokenFactoryMsg>> {
+        Ok(CosmosMsg::Custom(TokenFactoryMsg::CreateDenom {
+            subdenom: subdenom.into(),
+        }))
+    }
+
+    fn mint(
+        &self,
+        _sender:   &str,
+        coin:      Coin,
+        recipient: &str,
+    ) -> StdResult<Vec<CosmosMsg<TokenFactoryMsg>>> {
+        Ok(vec![CosmosMsg::Custom(TokenFactoryMsg::MintTokens {
+            denom:           coin.denom,
+            amount:          coin.amount,
+            mint_to_address: recipient.into(),
+        })])
+    }
+
+    fn burn(&self, sender: &str, coin: Coin) -> StdResult<CosmosMsg<TokenFactoryMsg>> {
+        Ok(CosmosMsg::Custom(TokenFactoryMsg::BurnTokens {
+            denom:             coin.denom,
+            amount:            coin.amount,
+            burn_from_address: sender.into(),
+        }))
+    }
+
+    fn denom_creation_fee(&self, querier: &QuerierWrapper) -> StdResult<Vec<Coin>> {
+        #[derive(Deserialize)]
+        #[serde(crate = "cosmwasm_schema::serde")]
+        struct Params {
+            #[serde(default)]
+            denom_creation_fee: Vec<Coin>,
+        }
+
+        let res: ParamsResponse<Params> = QuerierWrapper::<TokenFactoryQuery>::new(&**querier)
+            .query(&QueryRequest::Custom(TokenFactoryQuery::Params {}))?;
+
+        Ok(res.params.denom_creation_fee)
+    }
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, testing::MockQuerier, Binary, ContractResult, SystemResult};
+
+    use super::*;
+
+    #[test]
+    fn bindings_messages() {
+        assert_eq!(Bindings.denom("core", "hash"), "factory/core/hash");
+
+        assert_eq!(
+            Bindings.create_denom("core", "hash").unwrap(),
+            CosmosMsg::Custom(TokenFactoryMsg::CreateDenom {
+                subdenom: "hash".into(),
+            }),
+        );
+
+        assert_eq!(
+            Bindings.mint("core", coin(123, "factory/core/hash"), "larry").unwrap(),
+            vec![CosmosMsg::Custom(TokenFactoryMsg::MintTokens {
+                denom:           "factory/core/hash".into(),
+                amount:          Uint128::new(123),
+                mint_to_address: "larry".into(),
+            }),]
+        );
+
+        assert_eq!(
+            Bindings.burn("core", coin(456, "factory/core/hash")).unwrap(),
+            CosmosMsg::Custom(TokenFactoryMsg::BurnTokens {
+                denom:             "factory/core/hash".into(),
+                amount:            Uint128::new(456),
+                burn_from_address: "core".into(),
+            }),
+        );
+
+        let querier = MockQuerier::<TokenFactoryQuery>::new(&[]).with_custom_handler(|query| {
+            assert_eq!(query, &TokenFactoryQuery::Params {});
+            SystemResult::Ok(ContractResult::Ok(Binary::from(
+                br#"{"params":{"denom_creation_fee":[{"denom":"ustake","amount":"10"}]}}"#,
+            )))
+        });
+        let fee = Bindings.denom_creation_fee(&QuerierWrapper::new(&querier)).unwrap();
+        assert_eq!(fee, vec![coin(10, "ustake")]);
+    }
+
+    #[cfg(feature = "bindings")]
+    #[test]
+    fn selecting_bindings() {
+        let backend = crate::msg::TokenFactoryFlavor::Bindings.backend();
+        assert_eq!(
+            backend.create_denom("core", "hash").unwrap(),
+            CosmosMsg::Custom(TokenFactoryMsg::CreateDenom {
+                subdenom: "hash".into(),
+            }),
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use {
+    super::{query_params, stargate_msg, TokenBackend},
+    crate::{transfer::into_proto_coin, CustomMsg},
+    cosmwasm_schema::serde::Deserialize,
+    cosmwasm_std::{BankMsg, Coin, CosmosMsg, QuerierWrapper, StdResult},
+    osmosis_std::types::cosmos::base::v1beta1::Coin as ProtoCoin,
+};
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgCreateDenom {
+    #[prost(string, tag = "1")]
+    pub sender:   String,
+    #[prost(string, tag = "2")]
+    pub subdenom: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgMint {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(message, optional, tag = "2")]
+    pub amount: Option<ProtoCoin>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgBurn {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(message, optional, tag = "2")]
+    pub amount: Option<ProtoCoin>,
+}
+
+/// Injective's tokenfactory module.
+///
+/// Minting and burning only work on the sender's own balance, so tokens are
+/// first minted to the contract itself, then transferred to the recipient.
+pub struct Injective;
+
+impl TokenBackend<CustomMsg> for Injective {
+    fn create_denom(&self, sender: &str, subdenom: &str) -> StdResult<CosmosMsg<CustomMsg>> {
+        Ok(stargate_msg("/injective.tokenfactory.v1beta1.MsgCreateDenom", &MsgCreateDenom {
+            sender:   sender.into(),
+            subdenom: subdenom.into(),
+        }))
+    }
+
+    fn mint(
+        &self,
+        sender:    &str,
+        coin:      Coin,
+        recipient: &str,
+    ) -> StdResult<Vec<CosmosMsg<CustomMsg>>> {
+        Ok(vec![
+            stargate_msg("/injective.tokenfactory.v1beta1.MsgMint", &MsgMint {
+                sender: sender.into(),
+                amount: Some(into_proto_coin(coin.clone())),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.into(),
+                amount:     vec![coin],
+            }),
+        ])
+    }
+
+    fn burn(&self, sender: &str, coin: Coin) -> StdResult<CosmosMsg<CustomMsg>> {
+        Ok(stargate_msg("/injective.tokenfactory.v1beta1.MsgBurn", &MsgBurn {
+            sender: sender.into(),
+            amount: Some(into_proto_coin(coin)),
+        }))
+    }
+
+    fn denom_creation_fee(&self, querier: &QuerierWrapper) -> StdResult<Vec<Coin>> {
+        #[derive(Deserialize)]
+        #[serde(crate = "cosmwasm_schema::serde")]
+        struct Params {
+            #[serde(default)]
+            denom_creation_fee: Vec<Coin>,
+        }
+
+        query_params::<Params>(querier, "/injective.tokenfactory.v1beta1.Query/Params")
+            .map(|params| params.denom_creation_fee)
+    }
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::coin;
+
+    use super::*;
+    use crate::token_backend::tests::{decode, mock_querier, proto_coin};
+
+    #[test]
+    fn injective_messages() {
+        let msg = Injective.create_denom("core", "hash").unwrap();
+        let msg: MsgCreateDenom = decode(&msg, "/injective.tokenfactory.v1beta1.MsgCreateDenom");
+        assert_eq!(msg, MsgCreateDenom {
+            sender:   "core".into(),
+            subdenom: "hash".into(),
+        });
+
+        let msgs = Injective.mint("core", coin(123, "factory/core/hash"), "larry").unwrap();
+        assert_eq!(msgs.len(), 2);
+        let mint: MsgMint = decode(&msgs[0], "/injective.tokenfactory.v1beta1.MsgMint");
+        assert_eq!(mint, MsgMint {
+            sender: "core".into(),
+            amount: proto_coin(123, "factory/core/hash"),
+        });
+        assert_eq!(
+            msgs[1],
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "larry".into(),
+                amount:     vec![coin(123, "factory/core/hash")],
+            }),
+        );
+
+        let msg = Injective.burn("core", coin(456, "factory/core/hash")).unwrap();
+        let msg: MsgBurn = decode(&msg, "/injective.tokenfactory.v1beta1.MsgBurn");
+        assert_eq!(msg, MsgBurn {
+            sender: "core".into(),
+            amount: proto_coin(456, "factory/core/hash"),
+        });
+
+        let querier = mock_querier(
+            "/injective.tokenfactory.v1beta1.Query/Params",
+            r#"{"params":{"denom_creation_fee":[{"denom":"inj","amount":"100000000000000000"}]}}"#,
+        );
+        let fee = Injective.denom_creation_fee(&QuerierWrapper::new(&querier)).unwrap();
+        assert_eq!(fee, vec![coin(100000000000000000, "inj")]);
+    }
+}
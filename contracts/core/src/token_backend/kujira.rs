@@ -0,0 +1,125 @@
+use {
+    super::{query_params, stargate_msg, TokenBackend},
+    crate::{transfer::into_proto_coin, CustomMsg},
+    cosmwasm_schema::serde::Deserialize,
+    cosmwasm_std::{Coin, CosmosMsg, QuerierWrapper, StdResult},
+    osmosis_std::types::cosmos::base::v1beta1::Coin as ProtoCoin,
+};
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgCreateDenom {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(string, tag = "2")]
+    pub nonce:  String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgMint {
+    #[prost(string, tag = "1")]
+    pub sender:    String,
+    #[prost(message, optional, tag = "2")]
+    pub amount:    Option<ProtoCoin>,
+    #[prost(string, tag = "3")]
+    pub recipient: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgBurn {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(message, optional, tag = "2")]
+    pub amount: Option<ProtoCoin>,
+}
+
+/// Kujira's denom module.
+///
+/// The subdenom is called a "nonce" here. Tokens can be minted directly to the
+/// recipient.
+pub struct Kujira;
+
+impl TokenBackend<CustomMsg> for Kujira {
+    fn create_denom(&self, sender: &str, subdenom: &str) -> StdResult<CosmosMsg<CustomMsg>> {
+        Ok(stargate_msg("/kujira.denom.MsgCreateDenom", &MsgCreateDenom {
+            sender: sender.into(),
+            nonce:  subdenom.into(),
+        }))
+    }
+
+    fn mint(
+        &self,
+        sender:    &str,
+        coin:      Coin,
+        recipient: &str,
+    ) -> StdResult<Vec<CosmosMsg<CustomMsg>>> {
+        Ok(vec![stargate_msg("/kujira.denom.MsgMint", &MsgMint {
+            sender:    sender.into(),
+            amount:    Some(into_proto_coin(coin)),
+            recipient: recipient.into(),
+        })])
+    }
+
+    fn burn(&self, sender: &str, coin: Coin) -> StdResult<CosmosMsg<CustomMsg>> {
+        Ok(stargate_msg("/kujira.denom.MsgBurn", &MsgBurn {
+            sender: sender.into(),
+            amount: Some(into_proto_coin(coin)),
+        }))
+    }
+
+    fn denom_creation_fee(&self, querier: &QuerierWrapper) -> StdResult<Vec<Coin>> {
+        #[derive(Deserialize)]
+        #[serde(crate = "cosmwasm_schema::serde")]
+        struct Params {
+            #[serde(default)]
+            creation_fee: Vec<Coin>,
+        }
+
+        query_params::<Params>(querier, "/kujira.denom.Query/Params")
+            .map(|params| params.creation_fee)
+    }
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::coin;
+
+    use super::*;
+    use crate::token_backend::tests::{decode, mock_querier, proto_coin};
+
+    #[test]
+    fn kujira_messages() {
+        assert_eq!(Kujira.denom("core", "hash"), "factory/core/hash");
+
+        let msg = Kujira.create_denom("core", "hash").unwrap();
+        let msg: MsgCreateDenom = decode(&msg, "/kujira.denom.MsgCreateDenom");
+        assert_eq!(msg, MsgCreateDenom {
+            sender: "core".into(),
+            nonce:  "hash".into(),
+        });
+
+        let msgs = Kujira.mint("core", coin(123, "factory/core/hash"), "larry").unwrap();
+        assert_eq!(msgs.len(), 1);
+        let mint: MsgMint = decode(&msgs[0], "/kujira.denom.MsgMint");
+        assert_eq!(mint, MsgMint {
+            sender:    "core".into(),
+            amount:    proto_coin(123, "factory/core/hash"),
+            recipient: "larry".into(),
+        });
+
+        let msg = Kujira.burn("core", coin(456, "factory/core/hash")).unwrap();
+        let msg: MsgBurn = decode(&msg, "/kujira.denom.MsgBurn");
+        assert_eq!(msg, MsgBurn {
+            sender: "core".into(),
+            amount: proto_coin(456, "factory/core/hash"),
+        });
+
+        let querier = mock_querier(
+            "/kujira.denom.Query/Params",
+            r#"{"params":{"creation_fee":[{"denom":"ukuji","amount":"10000000"}]}}"#,
+        );
+        let fee = Kujira.denom_creation_fee(&QuerierWrapper::new(&querier)).unwrap();
+        assert_eq!(fee, vec![coin(10000000, "ukuji")]);
+    }
+}
@@ -0,0 +1,197 @@
+mod bindings;
+mod injective;
+mod kujira;
+mod osmosis;
+mod sei;
+
+use {
+    crate::{
+        msg::{TokenFactoryFlavor, VoucherBackend},
+        CustomMsg,
+    },
+    cosmwasm_schema::serde::Deserialize,
+    cosmwasm_std::{Coin, CosmosMsg, Empty, QuerierWrapper, QueryRequest, StdResult},
+};
+
+pub use self::{
+    bindings::{Bindings, TokenFactoryMsg, TokenFactoryQuery},
+    injective::Injective,
+    kujira::Kujira,
+    osmosis::{Neutron, Osmosis},
+    sei::Sei,
+};
+
+/// Creating, minting and burning native voucher tokens.
+///
+/// Each chain's tokenfactory module has slightly different messages, fee
+/// params and mint-to semantics. This trait hides these differences from the
+/// rest of the contract.
+pub trait TokenBackend<T = Empty> {
+    /// The full denom of a token created by `creator` with the given subdenom
+    fn denom(&self, creator: &str, subdenom: &str) -> String {
+        format!("factory/{creator}/{subdenom}")
+    }
+
+    /// Message for creating a new denom
+    fn create_denom(&self, sender: &str, subdenom: &str) -> StdResult<CosmosMsg<T>>;
+
+    /// Messages for minting tokens to the recipient.
+    ///
+    /// The last message in the returned vector is the one that delivers the
+    /// tokens to the recipient.
+    fn mint(&self, sender: &str, coin: Coin, recipient: &str) -> StdResult<Vec<CosmosMsg<T>>>;
+
+    /// Message for burning tokens held by the sender
+    fn burn(&self, sender: &str, coin: Coin) -> StdResult<CosmosMsg<T>>;
+
+    /// The fee charged for creating a new denom
+    fn denom_creation_fee(&self, querier: &QuerierWrapper) -> StdResult<Vec<Coin>>;
+}
+
+impl TokenFactoryFlavor {
+    pub fn backend(&self) -> &'static dyn TokenBackend<CustomMsg> {
+        match self {
+            TokenFactoryFlavor::Osmosis => &Osmosis,
+            TokenFactoryFlavor::Neutron => &Neutron,
+            TokenFactoryFlavor::Injective => &Injective,
+            TokenFactoryFlavor::Sei => &Sei,
+            TokenFactoryFlavor::Kujira => &Kujira,
+            #[cfg(feature = "bindings")]
+            TokenFactoryFlavor::Bindings => &Bindings,
+        }
+    }
+}
+
+impl VoucherBackend {
+    /// The tokenfactory backend, or `None` if vouchers are cw20 tokens.
+    pub fn token_factory(&self) -> Option<&'static dyn TokenBackend<CustomMsg>> {
+        match self {
+            VoucherBackend::TokenFactory {
+                flavor,
+            } => Some(flavor.backend()),
+            VoucherBackend::Cw20 {
+                ..
+            } => None,
+        }
+    }
+}
+
+/// Wrap a protobuf message into a stargate message
+fn stargate_msg(type_url: &str, msg: &impl prost::Message) -> CosmosMsg<CustomMsg> {
+    CosmosMsg::Stargate {
+        type_url: type_url.into(),
+        value:    msg.encode_to_vec().into(),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "cosmwasm_schema::serde")]
+struct ParamsResponse<P> {
+    params: P,
+}
+
+/// Query a tokenfactory module's params through a stargate query.
+///
+/// The chain must have whitelisted the query. The response is expected to be
+/// JSON-encoded, as is the case for whitelisted queries.
+fn query_params<P>(querier: &QuerierWrapper, path: &str) -> StdResult<P>
+where
+    P: for<'de> Deserialize<'de>,
+{
+    let res: ParamsResponse<P> = querier.query(&QueryRequest::Stargate {
+        path: path.into(),
+        data: vec![].into(),
+    })?;
+
+    Ok(res.params)
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {
+        cosmwasm_std::{
+            from_slice, Binary, ContractResult, Querier, QuerierResult, SystemError, SystemResult,
+        },
+        osmosis_std::types::cosmos::base::v1beta1::Coin as ProtoCoin,
+    };
+
+    use super::*;
+    use crate::transfer::into_proto_coin;
+
+    /// Querier that responds to a single stargate query path
+    pub struct StargateQuerier {
+        path:     String,
+        response: Binary,
+    }
+
+    impl Querier for StargateQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            match from_slice(bin_request) {
+                Ok(QueryRequest::<Empty>::Stargate {
+                    path,
+                    ..
+                }) if path == self.path => {
+                    SystemResult::Ok(ContractResult::Ok(self.response.clone()))
+                },
+                _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                    kind: "non-stargate or unknown path".into(),
+                }),
+            }
+        }
+    }
+
+    pub fn mock_querier(path: &str, response: &str) -> StargateQuerier {
+        StargateQuerier {
+            path:     path.into(),
+            response: response.as_bytes().into(),
+        }
+    }
+
+    /// Decode a stargate message, asserting its type URL
+    pub fn decode<M>(msg: &CosmosMsg<CustomMsg>, expected_type_url: &str) -> M
+    where
+        M: prost::Message + Default,
+    {
+        let CosmosMsg::Stargate {
+            type_url,
+            value,
+        } = msg
+        else {
+            panic!("not a stargate message: {msg:?}");
+        };
+        assert_eq!(type_url, expected_type_url);
+        M::decode(value.as_slice()).unwrap()
+    }
+
+    pub fn proto_coin(amount: u128, denom: &str) -> Option<ProtoCoin> {
+        Some(into_proto_coin(Coin::new(amount, denom)))
+    }
+
+    #[test]
+    fn selecting_backend() {
+        let querier = mock_querier(
+            "/osmosis.tokenfactory.v1beta1.Query/Params",
+            r#"{"params":{"denom_creation_fee":[{"denom":"uosmo","amount":"10000000"}]}}"#,
+        );
+
+        let backend = VoucherBackend::TokenFactory {
+            flavor: TokenFactoryFlavor::Osmosis,
+        };
+        let tokenfactory = backend.token_factory().unwrap();
+        let fee = tokenfactory.denom_creation_fee(&QuerierWrapper::new(&querier)).unwrap();
+        assert_eq!(fee, vec![Coin::new(10000000, "uosmo")]);
+
+        let backend = VoucherBackend::TokenFactory {
+            flavor: TokenFactoryFlavor::Kujira,
+        };
+        let msg = backend.token_factory().unwrap().create_denom("core", "hash").unwrap();
+        let _: kujira::MsgCreateDenom = decode(&msg, "/kujira.denom.MsgCreateDenom");
+
+        let backend = VoucherBackend::Cw20 {
+            code_id: 1,
+        };
+        assert!(backend.token_factory().is_none());
+    }
+}
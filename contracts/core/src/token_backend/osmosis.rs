@@ -0,0 +1,179 @@
+use {
+    super::{query_params, TokenBackend},
+    crate::{
+        transfer::{from_proto_coin, into_proto_coin},
+        CustomMsg,
+    },
+    cosmwasm_schema::serde::Deserialize,
+    cosmwasm_std::{BankMsg, Coin, CosmosMsg, QuerierWrapper, StdError, StdResult},
+    osmosis_std::types::osmosis::tokenfactory::v1beta1 as tokenfactory,
+};
+
+/// Osmosis' tokenfactory module.
+///
+/// Only supports minting to the sender, so tokens are first minted to the
+/// contract itself, then transferred to the recipient.
+pub struct Osmosis;
+
+impl TokenBackend<CustomMsg> for Osmosis {
+    fn create_denom(&self, sender: &str, subdenom: &str) -> StdResult<CosmosMsg<CustomMsg>> {
+        Ok(tokenfactory::MsgCreateDenom {
+            sender:   sender.into(),
+            subdenom: subdenom.into(),
+        }
+        .into())
+    }
+
+    fn mint(
+        &self,
+        sender:    &str,
+        coin:      Coin,
+        recipient: &str,
+    ) -> StdResult<Vec<CosmosMsg<CustomMsg>>> {
+        Ok(vec![
+            tokenfactory::MsgMint {
+                sender:          sender.into(),
+                mint_to_address: sender.into(),
+                amount:          Some(into_proto_coin(coin.clone())),
+            }
+            .into(),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.into(),
+                amount:     vec![coin],
+            }),
+        ])
+    }
+
+    fn burn(&self, sender: &str, coin: Coin) -> StdResult<CosmosMsg<CustomMsg>> {
+        Ok(burn_msg(sender, coin))
+    }
+
+    fn denom_creation_fee(&self, querier: &QuerierWrapper) -> StdResult<Vec<Coin>> {
+        tokenfactory::TokenfactoryQuerier::new(querier)
+            .params()?
+            .params
+            .ok_or_else(|| StdError::generic_err("params response does not contain params"))?
+            .denom_creation_fee
+            .into_iter()
+            .map(from_proto_coin)
+            .collect()
+    }
+}
+
+/// Neutron's tokenfactory module, a fork of Osmosis' that uses the same
+/// messages, but supports minting directly to the recipient.
+///
+/// The params query is not available through osmosis-std's querier, because
+/// Neutron only whitelists it as a JSON-encoded stargate query.
+pub struct Neutron;
+
+impl TokenBackend<CustomMsg> for Neutron {
+    fn create_denom(&self, sender: &str, subdenom: &str) -> StdResult<CosmosMsg<CustomMsg>> {
+        Osmosis.create_denom(sender, subdenom)
+    }
+
+    fn mint(
+        &self,
+        sender:    &str,
+        coin:      Coin,
+        recipient: &str,
+    ) -> StdResult<Vec<CosmosMsg<CustomMsg>>> {
+        Ok(vec![tokenfactory::MsgMint {
+            sender:          sender.into(),
+            mint_to_address: recipient.into(),
+            amount:          Some(into_proto_coin(coin)),
+        }
+        .into()])
+    }
+
+    fn burn(&self, sender: &str, coin: Coin) -> StdResult<CosmosMsg<CustomMsg>> {
+        Ok(burn_msg(sender, coin))
+    }
+
+    fn denom_creation_fee(&self, querier: &QuerierWrapper) -> StdResult<Vec<Coin>> {
+        #[derive(Deserialize)]
+        #[serde(crate = "cosmwasm_schema::serde")]
+        struct Params {
+            #[serde(default)]
+            denom_creation_fee: Vec<Coin>,
+        }
+
+        query_params::<Params>(querier, "/osmosis.tokenfactory.v1beta1.Query/Params")
+            .map(|params| params.denom_creation_fee)
+    }
+}
+
+fn burn_msg(sender: &str, coin: Coin) -> CosmosMsg<CustomMsg> {
+    tokenfactory::MsgBurn {
+        sender:            sender.into(),
+        burn_from_address: sender.into(),
+        amount:            Some(into_proto_coin(coin)),
+    }
+    .into()
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::coin;
+
+    use super::*;
+    use crate::token_backend::tests::{decode, mock_querier, proto_coin};
+
+    #[test]
+    fn osmosis_messages() {
+        assert_eq!(Osmosis.denom("core", "hash"), "factory/core/hash");
+
+        let msg = Osmosis.create_denom("core", "hash").unwrap();
+        let msg: tokenfactory::MsgCreateDenom =
+            decode(&msg, "/osmosis.tokenfactory.v1beta1.MsgCreateDenom");
+        assert_eq!(msg, tokenfactory::MsgCreateDenom {
+            sender:   "core".into(),
+            subdenom: "hash".into(),
+        });
+
+        let msgs = Osmosis.mint("core", coin(123, "factory/core/hash"), "larry").unwrap();
+        assert_eq!(msgs.len(), 2);
+        let mint: tokenfactory::MsgMint = decode(&msgs[0], "/osmosis.tokenfactory.v1beta1.MsgMint");
+        assert_eq!(mint, tokenfactory::MsgMint {
+            sender:          "core".into(),
+            mint_to_address: "core".into(),
+            amount:          proto_coin(123, "factory/core/hash"),
+        });
+        assert_eq!(
+            msgs[1],
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "larry".into(),
+                amount:     vec![coin(123, "factory/core/hash")],
+            }),
+        );
+
+        let msg = Osmosis.burn("core", coin(456, "factory/core/hash")).unwrap();
+        let msg: tokenfactory::MsgBurn = decode(&msg, "/osmosis.tokenfactory.v1beta1.MsgBurn");
+        assert_eq!(msg, tokenfactory::MsgBurn {
+            sender:            "core".into(),
+            burn_from_address: "core".into(),
+            amount:            proto_coin(456, "factory/core/hash"),
+        });
+    }
+
+    #[test]
+    fn neutron_messages() {
+        let msgs = Neutron.mint("core", coin(123, "factory/core/hash"), "larry").unwrap();
+        assert_eq!(msgs.len(), 1);
+        let mint: tokenfactory::MsgMint = decode(&msgs[0], "/osmosis.tokenfactory.v1beta1.MsgMint");
+        assert_eq!(mint, tokenfactory::MsgMint {
+            sender:          "core".into(),
+            mint_to_address: "larry".into(),
+            amount:          proto_coin(123, "factory/core/hash"),
+        });
+
+        let querier = mock_querier(
+            "/osmosis.tokenfactory.v1beta1.Query/Params",
+            r#"{"params":{"denom_creation_fee":[{"denom":"untrn","amount":"1000000"}]}}"#,
+        );
+        let fee = Neutron.denom_creation_fee(&QuerierWrapper::new(&querier)).unwrap();
+        assert_eq!(fee, vec![coin(1000000, "untrn")]);
+    }
+}
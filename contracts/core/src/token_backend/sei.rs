@@ -0,0 +1,120 @@
+use {
+    super::{stargate_msg, TokenBackend},
+    crate::{transfer::into_proto_coin, CustomMsg},
+    cosmwasm_std::{BankMsg, Coin, CosmosMsg, QuerierWrapper, StdResult},
+    osmosis_std::types::cosmos::base::v1beta1::Coin as ProtoCoin,
+};
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgCreateDenom {
+    #[prost(string, tag = "1")]
+    pub sender:   String,
+    #[prost(string, tag = "2")]
+    pub subdenom: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgMint {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(message, optional, tag = "2")]
+    pub amount: Option<ProtoCoin>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgBurn {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(message, optional, tag = "2")]
+    pub amount: Option<ProtoCoin>,
+}
+
+/// Sei's tokenfactory module.
+///
+/// Minting only works to the sender, so tokens are first minted to the
+/// contract itself, then transferred to the recipient. Sei doesn't charge a fee
+/// for creating denoms, other than gas.
+pub struct Sei;
+
+impl TokenBackend<CustomMsg> for Sei {
+    fn create_denom(&self, sender: &str, subdenom: &str) -> StdResult<CosmosMsg<CustomMsg>> {
+        Ok(stargate_msg("/seiprotocol.seichain.tokenfactory.MsgCreateDenom", &MsgCreateDenom {
+            sender:   sender.into(),
+            subdenom: subdenom.into(),
+        }))
+    }
+
+    fn mint(
+        &self,
+        sender:    &str,
+        coin:      Coin,
+        recipient: &str,
+    ) -> StdResult<Vec<CosmosMsg<CustomMsg>>> {
+        Ok(vec![
+            stargate_msg("/seiprotocol.seichain.tokenfactory.MsgMint", &MsgMint {
+                sender: sender.into(),
+                amount: Some(into_proto_coin(coin.clone())),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.into(),
+                amount:     vec![coin],
+            }),
+        ])
+    }
+
+    fn burn(&self, sender: &str, coin: Coin) -> StdResult<CosmosMsg<CustomMsg>> {
+        Ok(stargate_msg("/seiprotocol.seichain.tokenfactory.MsgBurn", &MsgBurn {
+            sender: sender.into(),
+            amount: Some(into_proto_coin(coin)),
+        }))
+    }
+
+    fn denom_creation_fee(&self, _querier: &QuerierWrapper) -> StdResult<Vec<Coin>> {
+        Ok(vec![])
+    }
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, testing::MockQuerier};
+
+    use super::*;
+    use crate::token_backend::tests::{decode, proto_coin};
+
+    #[test]
+    fn sei_messages() {
+        let msg = Sei.create_denom("core", "hash").unwrap();
+        let msg: MsgCreateDenom = decode(&msg, "/seiprotocol.seichain.tokenfactory.MsgCreateDenom");
+        assert_eq!(msg, MsgCreateDenom {
+            sender:   "core".into(),
+            subdenom: "hash".into(),
+        });
+
+        let msgs = Sei.mint("core", coin(123, "factory/core/hash"), "larry").unwrap();
+        assert_eq!(msgs.len(), 2);
+        let mint: MsgMint = decode(&msgs[0], "/seiprotocol.seichain.tokenfactory.MsgMint");
+        assert_eq!(mint, MsgMint {
+            sender: "core".into(),
+            amount: proto_coin(123, "factory/core/hash"),
+        });
+        assert_eq!(
+            msgs[1],
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "larry".into(),
+                amount:     vec![coin(123, "factory/core/hash")],
+            }),
+        );
+
+        let msg = Sei.burn("core", coin(456, "factory/core/hash")).unwrap();
+        let msg: MsgBurn = decode(&msg, "/seiprotocol.seichain.tokenfactory.MsgBurn");
+        assert_eq!(msg, MsgBurn {
+            sender: "core".into(),
+            amount: proto_coin(456, "factory/core/hash"),
+        });
+
+        let querier = MockQuerier::default();
+        assert!(Sei.denom_creation_fee(&QuerierWrapper::new(&querier)).unwrap().is_empty());
+    }
+}
@@ -1,80 +1,322 @@
 use {
-    crate::error::{Error, Result},
-    cosmwasm_std::{attr, Attribute, BankMsg, Coin, CosmosMsg, QuerierWrapper},
-    osmosis_std::types::{
-        cosmos::base::v1beta1::Coin as ProtoCoin, osmosis::tokenfactory::v1beta1 as tokenfactory,
+    super::TraceItem,
+    crate::{
+        error::{Error, Result},
+        msg::VoucherBackend,
+        state::{CONFIG, DENOM_ALIASES, DENOM_CREATION_POOL, ESCROWS, VOUCHER_SUPPLIES},
+        token_backend::TokenBackend,
+        CustomMsg,
     },
+    cosmwasm_schema::cw_serde,
+    cosmwasm_std::{
+        attr, instantiate2_address, to_binary, Attribute, BankMsg, Binary, Coin, CosmosMsg, Deps,
+        QuerierWrapper, StdError, StdResult, Storage, Uint128, WasmMsg,
+    },
+    cw20::{Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse, TokenInfoResponse},
+    osmosis_std::types::cosmos::base::v1beta1::Coin as ProtoCoin,
 };
 
 pub fn mint(
-    sender: impl Into<String> + Clone,
-    to:     impl Into<String>,
-    coin:   Coin,
-    msgs:   &mut Vec<CosmosMsg>,
-    attrs:  &mut Vec<Attribute>,
-) {
+    storage: &mut dyn Storage,
+    backend: &VoucherBackend,
+    sender:  &str,
+    to:      &str,
+    coin:    Coin,
+    msgs:    &mut Vec<CosmosMsg<CustomMsg>>,
+    attrs:   &mut Vec<Attribute>,
+) -> Result<()> {
     attrs.push(attr("coin", coin.to_string()));
     attrs.push(attr("action", "mint"));
-    msgs.push(
-        tokenfactory::MsgMint {
-            sender: sender.clone().into(),
-            mint_to_address: sender.into(),
-            amount: Some(into_proto_coin(coin.clone())),
-        }
-        .into(),
-    );
-    msgs.push(
-        BankMsg::Send {
-            to_address: to.into(),
-            amount: vec![coin],
+    add_voucher_supply(storage, &coin)?;
+    msgs.extend(mint_msgs(backend, sender, to, coin)?);
+    Ok(())
+}
+
+/// Compose the messages for minting a voucher to the recipient. The last
+/// message is the one that delivers the tokens to the recipient.
+pub fn mint_msgs(
+    backend: &VoucherBackend,
+    sender:  &str,
+    to:      &str,
+    coin:    Coin,
+) -> Result<Vec<CosmosMsg<CustomMsg>>> {
+    // cw20 vouchers can be minted directly to the recipient
+    if let Some(contract_addr) = parse_cw20_denom(&coin.denom) {
+        return Ok(vec![WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: to.into(),
+                amount:    coin.amount,
+            })?,
+            funds: vec![],
         }
-        .into(),
-    );
+        .into()]);
+    }
+
+    Ok(token_factory(backend, &coin.denom)?.mint(sender, coin, to)?)
 }
 
 pub fn burn(
-    sender: impl Into<String> + Clone,
-    coin:   Coin,
-    msgs:   &mut Vec<CosmosMsg>,
-    attrs:  &mut Vec<Attribute>,
-) {
+    storage: &mut dyn Storage,
+    backend: &VoucherBackend,
+    sender:  &str,
+    coin:    Coin,
+    msgs:    &mut Vec<CosmosMsg<CustomMsg>>,
+    attrs:   &mut Vec<Attribute>,
+) -> Result<()> {
     attrs.push(attr("coin", coin.to_string()));
     attrs.push(attr("action", "burn"));
-    msgs.push(
-        tokenfactory::MsgBurn {
-            sender: sender.clone().into(),
-            burn_from_address: sender.into(),
-            amount: Some(into_proto_coin(coin)),
-        }
-        .into(),
-    );
+    deduct_voucher_supply(storage, &coin)?;
+
+    if let Some(contract_addr) = parse_cw20_denom(&coin.denom) {
+        msgs.push(
+            WasmMsg::Execute {
+                contract_addr: contract_addr.into(),
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: coin.amount,
+                })?,
+                funds: vec![],
+            }
+            .into(),
+        );
+        return Ok(());
+    }
+
+    msgs.push(token_factory(backend, &coin.denom)?.burn(sender, coin)?);
+
+    Ok(())
+}
+
+// native vouchers can only exist if the tokenfactory backend is used
+fn token_factory(
+    backend: &VoucherBackend,
+    denom:   &str,
+) -> Result<&'static dyn TokenBackend<CustomMsg>> {
+    backend.token_factory().ok_or_else(|| Error::NativeVoucherUnsupported {
+        denom: denom.into(),
+    })
 }
 
 pub fn release(
-    coin:  Coin,
-    to:    impl Into<String>,
-    msgs:  &mut Vec<CosmosMsg>,
-    attrs: &mut Vec<Attribute>,
-) {
+    storage:    &mut dyn Storage,
+    channel_id: &str,
+    coin:       Coin,
+    to:         impl Into<String>,
+    msgs:       &mut Vec<CosmosMsg<CustomMsg>>,
+    attrs:      &mut Vec<Attribute>,
+) -> Result<()> {
     attrs.push(attr("coin", coin.to_string()));
     attrs.push(attr("action", "release"));
-    msgs.push(
-        BankMsg::Send {
-            to_address: to.into(),
-            amount: vec![coin],
-        }
-        .into(),
-    );
+    deduct_escrow(storage, channel_id, &coin)?;
+    msgs.push(send_msg(coin, to)?);
+    Ok(())
 }
 
-pub fn escrow(coin: &Coin, attrs: &mut Vec<Attribute>) {
+pub fn escrow(
+    storage:    &mut dyn Storage,
+    channel_id: &str,
+    coin:       &Coin,
+    attrs:      &mut Vec<Attribute>,
+) -> StdResult<()> {
     attrs.push(attr("coin", coin.to_string()));
     attrs.push(attr("action", "escrow"));
+    add_escrow(storage, channel_id, coin)
+}
+
+/// Record tokens being escrowed for a transfer through the channel
+pub fn add_escrow(storage: &mut dyn Storage, channel_id: &str, coin: &Coin) -> StdResult<()> {
+    ESCROWS.update(storage, (channel_id, &coin.denom), |escrowed| -> StdResult<_> {
+        Ok(escrowed.unwrap_or_default().checked_add(coin.amount)?)
+    })?;
+    Ok(())
+}
+
+/// Record tokens being released from the channel's escrow.
+///
+/// No more tokens can be released through a channel than were escrowed for it,
+/// so that a counterparty can't take the tokens sent through other channels.
+pub fn deduct_escrow(storage: &mut dyn Storage, channel_id: &str, coin: &Coin) -> Result<()> {
+    let key = (channel_id, coin.denom.as_str());
+    let escrowed = ESCROWS.may_load(storage, key)?.unwrap_or_default();
+
+    let Ok(remaining) = escrowed.checked_sub(coin.amount) else {
+        return Err(Error::InsufficientEscrow {
+            channel_id: channel_id.into(),
+            escrowed:   Coin::new(escrowed.u128(), &coin.denom),
+            amount:     coin.clone(),
+        });
+    };
+
+    if remaining.is_zero() {
+        ESCROWS.remove(storage, key);
+    } else {
+        ESCROWS.save(storage, key, &remaining)?;
+    }
+
+    Ok(())
 }
 
-/// Combine a creator address and a subdenom into the tokenfactory full denom
-pub fn construct_denom(creator: &str, subdenom: &str) -> String {
-    format!("factory/{creator}/{subdenom}")
+/// Record vouchers being minted
+pub fn add_voucher_supply(storage: &mut dyn Storage, coin: &Coin) -> StdResult<()> {
+    VOUCHER_SUPPLIES.update(storage, &coin.denom, |supply| -> StdResult<_> {
+        Ok(supply.unwrap_or_default().checked_add(coin.amount)?)
+    })?;
+    Ok(())
+}
+
+/// Record vouchers being burned
+pub fn deduct_voucher_supply(storage: &mut dyn Storage, coin: &Coin) -> StdResult<()> {
+    let supply = VOUCHER_SUPPLIES.may_load(storage, &coin.denom)?.unwrap_or_default();
+    let remaining = supply.checked_sub(coin.amount)?;
+
+    if remaining.is_zero() {
+        VOUCHER_SUPPLIES.remove(storage, &coin.denom);
+    } else {
+        VOUCHER_SUPPLIES.save(storage, &coin.denom, &remaining)?;
+    }
+
+    Ok(())
+}
+
+/// Combine a cw20 contract address into the denom used to represent the token
+/// in ICS-999
+pub fn cw20_denom(contract_addr: &str) -> String {
+    format!("{CW20_DENOM_PREFIX}{contract_addr}")
+}
+
+/// If the denom represents a cw20 token, return the token's contract address
+pub fn parse_cw20_denom(denom: &str) -> Option<&str> {
+    denom.strip_prefix(CW20_DENOM_PREFIX)
+}
+
+const CW20_DENOM_PREFIX: &str = "cw20:";
+
+/// Derive the denom of the voucher token corresponding to a trace, depending on
+/// the voucher backend:
+/// - tokenfactory: `factory/{contract_addr}/{trace_hash}`
+/// - cw20: `cw20:{token_addr}`, where the token's address is predicted from the
+///   trace hash, which is used as the salt when instantiating it
+pub fn derive_voucher_denom(
+    deps:          Deps,
+    contract_addr: &str,
+    trace:         &TraceItem,
+) -> StdResult<String> {
+    match CONFIG.load(deps.storage)?.voucher_backend {
+        VoucherBackend::TokenFactory {
+            flavor,
+        } => Ok(flavor.backend().denom(contract_addr, &trace.hash().to_hex())),
+        VoucherBackend::Cw20 {
+            code_id,
+        } => {
+            let code_res = deps.querier.query_wasm_code_info(code_id)?;
+            let token_addr_raw = instantiate2_address(
+                &code_res.checksum,
+                &deps.api.addr_canonicalize(contract_addr)?,
+                trace.hash().as_slice(),
+            )
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+            let token_addr = deps.api.addr_humanize(&token_addr_raw)?;
+            Ok(cw20_denom(token_addr.as_str()))
+        },
+    }
+}
+
+/// Derive the denom of the voucher token corresponding to a trace.
+///
+/// If the trace was remapped following a channel replacement, return the denom
+/// originally created for it instead, so that vouchers received through the old
+/// and the new channel remain fungible.
+pub fn voucher_denom(deps: Deps, contract_addr: &str, trace: &TraceItem) -> StdResult<String> {
+    let denom = derive_voucher_denom(deps, contract_addr, trace)?;
+    Ok(DENOM_ALIASES.may_load(deps.storage, &denom)?.unwrap_or(denom))
+}
+
+/// Instantiate message of the cw20-base contract
+#[cw_serde]
+pub struct Cw20InstantiateMsg {
+    pub name:             String,
+    pub symbol:           String,
+    pub decimals:         u8,
+    pub initial_balances: Vec<Cw20Coin>,
+    pub mint:             Option<MinterResponse>,
+}
+
+/// Compose the message for instantiating the cw20 voucher token of a trace.
+///
+/// cw20-base requires the name to be 3-50 characters, and the symbol to be 3-12
+/// letters or dashes, so we derive them from the base denom accordingly. The
+/// number of decimals can't be known, so we go with the Cosmos convention of 6.
+pub fn cw20_voucher_instantiate_msg(
+    code_id:       u64,
+    contract_addr: &str,
+    trace:         &TraceItem,
+) -> StdResult<WasmMsg> {
+    let name = format!("ICS-999 {}", trace.base_denom).chars().take(50).collect();
+
+    let mut symbol = trace
+        .base_denom
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .take(12)
+        .collect::<String>()
+        .to_uppercase();
+    if symbol.len() < 3 {
+        symbol = "VOUCHER".into();
+    }
+
+    Ok(WasmMsg::Instantiate2 {
+        code_id,
+        msg: to_binary(&Cw20InstantiateMsg {
+            name,
+            symbol,
+            decimals: 6,
+            initial_balances: vec![],
+            mint: Some(MinterResponse {
+                minter: contract_addr.into(),
+                cap:    None,
+            }),
+        })?,
+        funds: vec![],
+        admin: Some(contract_addr.into()),
+        label: format!("one-core/voucher/{}", trace.hash()),
+        salt: Binary(trace.hash().to_vec()),
+    })
+}
+
+/// Compose the message for sending a native or cw20 token
+pub fn send_msg(coin: Coin, to: impl Into<String>) -> StdResult<CosmosMsg<CustomMsg>> {
+    if let Some(contract_addr) = parse_cw20_denom(&coin.denom) {
+        return Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: to.into(),
+                amount:    coin.amount,
+            })?,
+            funds: vec![],
+        }
+        .into());
+    }
+
+    Ok(BankMsg::Send {
+        to_address: to.into(),
+        amount: vec![coin],
+    }
+    .into())
+}
+
+/// Query the total supply of a native or cw20 token.
+///
+/// A cw20 voucher that is about to be created doesn't exist yet, so the query
+/// fails. Its supply is zero in this case.
+pub fn total_supply(querier: &QuerierWrapper, denom: &str) -> StdResult<Uint128> {
+    if let Some(contract_addr) = parse_cw20_denom(denom) {
+        return Ok(querier
+            .query_wasm_smart::<TokenInfoResponse>(contract_addr, &Cw20QueryMsg::TokenInfo {})
+            .map(|res| res.total_supply)
+            .unwrap_or_default());
+    }
+
+    Ok(querier.query_supply(denom)?.amount)
 }
 
 /// Convert a cosmwasm_std::Coin into a /cosmos.base.v1beta1.coin
@@ -85,20 +327,35 @@ pub fn into_proto_coin(coin: Coin) -> ProtoCoin {
     }
 }
 
-/// Assert that denom creation fee is zero.
+/// Pay the tokenfactory denom creation fee out of the denom creation pool.
 ///
-/// We don't have the money to pay the fee. If the fee is non-zero then we
-/// simply refuse to complete the transfer.
-pub fn assert_free_denom_creation(querier: &QuerierWrapper) -> Result<()> {
-    let fee = tokenfactory::TokenfactoryQuerier::new(querier)
-        .params()?
-        .params
-        .expect("params response does not contain params")
-        .denom_creation_fee;
-
-    if !fee.is_empty() {
-        return Err(Error::NonZeroTokenCreationFee);
+/// The pool is funded by the admin. Error if it doesn't hold enough to cover the
+/// fee, which is queried from the voucher backend beforehand.
+pub fn pay_denom_creation_fee(store: &mut dyn Storage, fee: &[Coin]) -> Result<()> {
+    for coin in fee {
+        let balance = DENOM_CREATION_POOL.may_load(store, &coin.denom)?.unwrap_or_default();
+
+        let Ok(balance) = balance.checked_sub(coin.amount) else {
+            return Err(Error::InsufficientDenomCreationPool {
+                fee:     coin.clone(),
+                balance: Coin::new(balance.u128(), &coin.denom),
+            });
+        };
+
+        DENOM_CREATION_POOL.save(store, &coin.denom, &balance)?;
     }
 
     Ok(())
 }
+
+/// Convert a /cosmos.base.v1beta1.coin into a cosmwasm_std::Coin
+pub fn from_proto_coin(coin: ProtoCoin) -> StdResult<Coin> {
+    let amount = coin.amount.parse::<Uint128>().map_err(|err| {
+        StdError::generic_err(format!("invalid coin amount `{}`: {err}", coin.amount))
+    })?;
+
+    Ok(Coin {
+        denom: coin.denom,
+        amount,
+    })
+}
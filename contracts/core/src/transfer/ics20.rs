@@ -0,0 +1,159 @@
+use {
+    super::TraceItem,
+    cosmwasm_schema::serde::Deserialize,
+    cosmwasm_std::{HexBinary, IbcEndpoint, QuerierWrapper, QueryRequest, StdError, StdResult},
+    sha2::{Digest, Sha256},
+};
+
+const ICS20_DENOM_PREFIX: &str = "ibc/";
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct QueryDenomTraceRequest {
+    #[prost(string, tag = "1")]
+    hash: String,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "cosmwasm_schema::serde")]
+struct QueryDenomTraceResponse {
+    denom_trace: DenomTrace,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "cosmwasm_schema::serde")]
+struct DenomTrace {
+    path:       String,
+    base_denom: String,
+}
+
+/// If the denom is an `ibc/{hash}` denom created by the ICS-20 transfer module,
+/// query the chain for its denom trace, and convert it to an ICS-999 trace.
+///
+/// ICS-20 prefixes each hop to the front of the path, while ICS-999 appends it
+/// to the end, so the steps are reversed. In both protocols, each step is the
+/// endpoint on the chain that received the token.
+///
+/// The chain must have whitelisted the `DenomTrace` stargate query.
+pub fn ics20_trace(querier: &QuerierWrapper, denom: &str) -> StdResult<Option<TraceItem>> {
+    let Some(hash) = denom.strip_prefix(ICS20_DENOM_PREFIX) else {
+        return Ok(None);
+    };
+
+    let res: QueryDenomTraceResponse = querier.query(&QueryRequest::Stargate {
+        path: "/ibc.applications.transfer.v1.Query/DenomTrace".into(),
+        data: prost::Message::encode_to_vec(&QueryDenomTraceRequest {
+            hash: hash.into(),
+        })
+        .into(),
+    })?;
+
+    let segments = res.denom_trace.path.split('/').collect::<Vec<_>>();
+    if segments.len() % 2 != 0 {
+        return Err(StdError::generic_err(format!(
+            "invalid ICS-20 path `{}` for denom `{denom}`",
+            res.denom_trace.path,
+        )));
+    }
+
+    Ok(Some(TraceItem {
+        base_denom: res.denom_trace.base_denom,
+        path:       segments
+            .chunks(2)
+            .rev()
+            .map(|step| IbcEndpoint {
+                port_id:    step[0].into(),
+                channel_id: step[1].into(),
+            })
+            .collect(),
+    }))
+}
+
+impl TraceItem {
+    /// The `ibc/{hash}` denom that the ICS-20 transfer module would give the
+    /// token described by this trace.
+    ///
+    /// Only meaningful if all steps in the path are ICS-20 hops, which is the
+    /// case for traces resolved by `ics20_trace`.
+    pub fn ics20_denom(&self) -> String {
+        let mut full_path = self
+            .path
+            .iter()
+            .rev()
+            .map(|step| format!("{}/{}/", step.port_id, step.channel_id))
+            .collect::<String>();
+        full_path.push_str(&self.base_denom);
+
+        let hash = HexBinary::from(Sha256::digest(full_path.as_bytes()).to_vec());
+
+        format!("{ICS20_DENOM_PREFIX}{}", hash.to_hex().to_uppercase())
+    }
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{testing::MockQuerier, Empty};
+
+    use super::*;
+    use crate::token_backend::tests::mock_querier;
+
+    const ATOM_ON_OSMOSIS: &str =
+        "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+    #[test]
+    fn resolving_ics20_trace() {
+        // not an ICS-20 denom: no query is made
+        {
+            let querier = MockQuerier::<Empty>::new(&[]);
+            let trace = ics20_trace(&QuerierWrapper::new(&querier), "uosmo").unwrap();
+            assert!(trace.is_none());
+        }
+
+        // single hop
+        {
+            let querier = mock_querier(
+                "/ibc.applications.transfer.v1.Query/DenomTrace",
+                r#"{"denom_trace":{"path":"transfer/channel-0","base_denom":"uatom"}}"#,
+            );
+            let trace = ics20_trace(&QuerierWrapper::new(&querier), ATOM_ON_OSMOSIS).unwrap();
+            let trace = trace.unwrap();
+            assert_eq!(trace, TraceItem {
+                base_denom: "uatom".into(),
+                path:       vec![IbcEndpoint {
+                    port_id:    "transfer".into(),
+                    channel_id: "channel-0".into(),
+                }],
+            });
+            assert_eq!(trace.ics20_denom(), ATOM_ON_OSMOSIS);
+        }
+
+        // multiple hops: the most recent one is the last step in the path
+        {
+            let querier = mock_querier(
+                "/ibc.applications.transfer.v1.Query/DenomTrace",
+                r#"{"denom_trace":{"path":"transfer/channel-1/transfer/channel-0","base_denom":"uatom"}}"#,
+            );
+            let trace = ics20_trace(&QuerierWrapper::new(&querier), "ibc/ABCD").unwrap();
+            assert_eq!(trace.unwrap().path, vec![
+                IbcEndpoint {
+                    port_id:    "transfer".into(),
+                    channel_id: "channel-0".into(),
+                },
+                IbcEndpoint {
+                    port_id:    "transfer".into(),
+                    channel_id: "channel-1".into(),
+                },
+            ]);
+        }
+
+        // malformed path
+        {
+            let querier = mock_querier(
+                "/ibc.applications.transfer.v1.Query/DenomTrace",
+                r#"{"denom_trace":{"path":"transfer","base_denom":"uatom"}}"#,
+            );
+            assert!(ics20_trace(&QuerierWrapper::new(&querier), "ibc/ABCD").is_err());
+        }
+    }
+}
@@ -1,4 +1,5 @@
 mod helpers;
+mod ics20;
 mod trace;
 
-pub use {helpers::*, trace::TraceItem};
+pub use {helpers::*, ics20::ics20_trace, trace::TraceItem};
@@ -1,10 +1,12 @@
 use {
+    cosmwasm_schema::cw_serde,
     cosmwasm_std::{Coin, OverflowError, Uint128},
     std::{collections::BTreeMap, fmt},
 };
 
 // denom => amount
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[cw_serde]
+#[derive(Eq)]
 pub struct Coins(BTreeMap<String, Uint128>);
 
 // UNSAFE: because we don't check for duplicate denoms or zero amounts
@@ -63,6 +65,21 @@ impl Coins {
         *amount = amount.checked_add(new_coin.amount)?;
         Ok(())
     }
+
+    /// The amount of the given denom, zero if there is none
+    pub fn amount_of(&self, denom: &str) -> Uint128 {
+        self.0.get(denom).copied().unwrap_or_default()
+    }
+
+    pub fn deduct(&mut self, coin: Coin) -> Result<(), OverflowError> {
+        let amount = self.amount_of(&coin.denom).checked_sub(coin.amount)?;
+        if amount.is_zero() {
+            self.0.remove(&coin.denom);
+        } else {
+            self.0.insert(coin.denom, amount);
+        }
+        Ok(())
+    }
 }
 
 // ----------------------------------- Tests -----------------------------------
@@ -104,4 +121,20 @@ mod tests {
         coins2.add(coin(1, "umars")).unwrap();
         assert_ne!(coins1, coins2);
     }
+
+    #[test]
+    fn deducting() {
+        let mut coins = Coins::from(vec![coin(23456, "uastro"), coin(88888, "umars")]);
+
+        coins.deduct(coin(3456, "uastro")).unwrap();
+        assert_eq!(coins.amount_of("uastro").u128(), 20000);
+
+        // deducting the entire amount removes the denom
+        coins.deduct(coin(88888, "umars")).unwrap();
+        assert_eq!(coins, Coins::from(vec![coin(20000, "uastro")]));
+
+        // can't deduct more than there is
+        assert!(coins.deduct(coin(20001, "uastro")).is_err());
+        assert!(coins.deduct(coin(1, "uosmo")).is_err());
+    }
 }
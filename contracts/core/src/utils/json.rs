@@ -0,0 +1,91 @@
+use {
+    cosmwasm_schema::serde::{
+        de::{MapAccess, SeqAccess, Visitor},
+        Deserialize, Deserializer,
+    },
+    std::fmt,
+};
+
+/// A JSON value, with strings, numbers, booleans and null all represented by
+/// their text.
+///
+/// We can't use `serde_json::Value`, as it supports floating point numbers,
+/// which are not allowed in CosmWasm contracts.
+#[derive(Clone)]
+pub enum Json {
+    Scalar(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Find the value under an object key or an array index
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Scalar(_) => None,
+            Json::Array(items) => items.get(key.parse::<usize>().ok()?),
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Json {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(JsonVisitor)
+    }
+}
+
+struct JsonVisitor;
+
+impl<'de> Visitor<'de> for JsonVisitor {
+    type Value = Json;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Json, E> {
+        Ok(Json::Scalar("null".into()))
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Json, E> {
+        Ok(Json::Scalar(v.to_string()))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Json, E> {
+        Ok(Json::Scalar(v.to_string()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Json, E> {
+        Ok(Json::Scalar(v.to_string()))
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Json, E> {
+        Ok(Json::Scalar(v.into()))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Json, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = vec![];
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Json::Array(items))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> std::result::Result<Json, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = vec![];
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Json::Object(entries))
+    }
+}
@@ -1,3 +1,5 @@
 mod coins;
+mod json;
+mod storage_buffer;
 
-pub use self::coins::Coins;
+pub use self::{coins::Coins, json::Json, storage_buffer::StorageBuffer};
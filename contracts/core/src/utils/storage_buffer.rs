@@ -0,0 +1,123 @@
+use {
+    cosmwasm_std::{Order, Record, Storage},
+    std::collections::BTreeMap,
+};
+
+/// Storage that holds writes in memory on top of another storage, so that they
+/// can be either written to it all at once, or discarded.
+pub struct StorageBuffer<'a> {
+    inner: &'a mut dyn Storage,
+
+    // key => value, or None if the key is removed
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<'a> StorageBuffer<'a> {
+    pub fn new(inner: &'a mut dyn Storage) -> Self {
+        Self {
+            inner,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    /// Write the buffered changes to the underlying storage
+    pub fn commit(self) {
+        for (key, value) in self.writes {
+            match value {
+                Some(value) => self.inner.set(&key, &value),
+                None => self.inner.remove(&key),
+            }
+        }
+    }
+}
+
+impl Storage for StorageBuffer<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(value) => value.clone(),
+            None => self.inner.get(key),
+        }
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end:   Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        let mut records: BTreeMap<_, _> =
+            self.inner.range(start, end, Order::Ascending).collect();
+
+        let in_range = |key: &[u8]| {
+            start.map_or(true, |start| key >= start) && end.map_or(true, |end| key < end)
+        };
+
+        for (key, value) in self.writes.iter().filter(|(key, _)| in_range(key)) {
+            match value {
+                Some(value) => records.insert(key.clone(), value.clone()),
+                None => records.remove(key),
+            };
+        }
+
+        match order {
+            Order::Ascending => Box::new(records.into_iter()),
+            Order::Descending => Box::new(records.into_iter().rev()),
+        }
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::MemoryStorage;
+
+    use super::*;
+
+    #[test]
+    fn buffering_writes() {
+        let mut storage = MemoryStorage::new();
+        storage.set(b"a", b"1");
+        storage.set(b"b", b"2");
+        storage.set(b"c", b"3");
+
+        let write = |buffer: &mut StorageBuffer| {
+            buffer.set(b"b", b"20");
+            buffer.remove(b"c");
+            buffer.set(b"d", b"4");
+        };
+
+        // the writes are visible through the buffer, but not written yet
+        let mut buffer = StorageBuffer::new(&mut storage);
+        write(&mut buffer);
+        assert_eq!(buffer.get(b"b"), Some(b"20".to_vec()));
+        assert_eq!(buffer.get(b"c"), None);
+        assert_eq!(buffer.range(Some(b"b"), None, Order::Descending).collect::<Vec<_>>(), vec![
+            (b"d".to_vec(), b"4".to_vec()),
+            (b"b".to_vec(), b"20".to_vec()),
+        ]);
+
+        // discarded
+        drop(buffer);
+        assert_eq!(storage.get(b"b"), Some(b"2".to_vec()));
+        assert_eq!(storage.get(b"c"), Some(b"3".to_vec()));
+
+        // committed
+        let mut buffer = StorageBuffer::new(&mut storage);
+        write(&mut buffer);
+        buffer.commit();
+        assert_eq!(storage.range(None, None, Order::Ascending).collect::<Vec<_>>(), vec![
+            (b"a".to_vec(), b"1".to_vec()),
+            (b"b".to_vec(), b"20".to_vec()),
+            (b"d".to_vec(), b"4".to_vec()),
+        ]);
+    }
+}
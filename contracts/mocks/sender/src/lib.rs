@@ -107,7 +107,11 @@ pub fn execute(deps: DepsMut, _: Env, _: MessageInfo, msg: ExecuteMsg) -> StdRes
                     msg: to_binary(&one_core::msg::ExecuteMsg::Dispatch {
                         connection_id,
                         actions,
-                        timeout: None, // use the default timeout set by one-core
+                        timeout:            None, // use the default timeout set by one-core
+                        fee:                None,
+                        allow_failure:      false,
+                        callback_gas_limit: None,
+                        callback:           None,
                     })?,
                     funds: funds.into(),
                 }))
@@ -117,6 +121,7 @@ pub fn execute(deps: DepsMut, _: Env, _: MessageInfo, msg: ExecuteMsg) -> StdRes
             endpoint,
             sequence,
             outcome,
+            ..
         }) => {
             OUTCOMES.save(deps.storage, (&endpoint.port_id, &endpoint.channel_id, sequence), &outcome)?;
 
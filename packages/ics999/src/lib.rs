@@ -1,6 +1,6 @@
 use {
-    cosmwasm_schema::cw_serde,
-    cosmwasm_std::{Binary, IbcEndpoint, IbcOrder, Uint128},
+    cosmwasm_schema::{cw_serde, serde::{self, Deserialize}},
+    cosmwasm_std::{Binary, Coin, Empty, IbcEndpoint, IbcOrder, QueryRequest, Uint128},
 };
 
 // ---------------------------------- channel ----------------------------------
@@ -8,8 +8,11 @@ use {
 /// Expected channel packet ordering rule
 pub const ORDER: IbcOrder = IbcOrder::Unordered;
 
-/// Expected channel version string
-pub const VERSION: &str = "ics999-1";
+/// Expected channel version string.
+///
+/// Bumped whenever the format of packet data or acks changes, so that chains
+/// that can't decode each other's packets can't open a channel.
+pub const VERSION: &str = "ics999-2";
 
 // ---------------------------------- packet -----------------------------------
 
@@ -20,22 +23,70 @@ pub struct PacketData {
     pub controller: String,
 
     /// Actions to take.
-    /// The actions will be executed in order and atomically.
+    /// The actions will be executed in order and, unless `allow_failure` is
+    /// set, atomically.
     pub actions: Vec<Action>,
 
     /// Traces of each token that is being transferred.
     /// Receiver chain uses this to determine whether it's the sender or sink.
-    /// Must include ALL tokens that are being transferred.
+    /// Must include ALL tokens that are being transferred, including those in
+    /// `Forward` actions.
     pub traces: Vec<Trace>,
+
+    /// Only set in packets relaying the outcome of a forwarded packet back to
+    /// the chain it was forwarded from. Such packets contain no actions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forward_ack: Option<ForwardAck>,
+
+    /// If set, a failed action doesn't fail the packet. Instead, only its own
+    /// effects are reverted, it gets a `Failed` result, and the rest of the
+    /// actions proceed. The tokens of failed `Transfer` and `Forward` actions
+    /// are refunded to the sender.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_failure: bool,
 }
 
+/// The outcome of a packet that the receiving chain forwarded on behalf of a
+/// packet sent by this chain.
+///
+/// IBC acks can't be deferred, so the intermediate chain acks the original
+/// packet right away, and relays the outcome of the forwarded packet in a
+/// separate packet once it is known.
+#[cw_serde]
+pub struct ForwardAck {
+    /// Sequence of the original packet, which contained the `Forward` action
+    pub sequence: u64,
+
+    /// Channel on the intermediate chain through which the packet was forwarded
+    pub forwarded_channel_id: String,
+
+    /// Sequence of the forwarded packet
+    pub forwarded_sequence: u64,
+
+    /// Outcome of the forwarded packet, including those of any packets it in
+    /// turn forwarded
+    pub outcome: PacketOutcome,
+}
+
+/// An action to be executed on the receiving chain.
+///
+/// The recipient of `Transfer` and the message of `Execute` may refer to the
+/// results of earlier actions in the same packet with placeholders of the form
+/// `{{results.<index>.<path>}}`, which are substituted right before the action
+/// is executed. `index` is the index of the earlier action, and `path` the
+/// dot-separated object keys and array indexes in the JSON form of its result,
+/// e.g. `{{results.0.register_account.address}}`. Binary values, such as the
+/// data of `Execute` results and query responses, are decoded as JSON if the
+/// path continues into them, e.g. `{{results.1.execute.data.return_amount}}`.
+/// The path must lead to a string, number, boolean or null.
 #[cw_serde]
 pub enum Action {
     /// Send one or more tokens to a recipient
     Transfer {
         denom: String,
         amount: Uint128,
-        /// If not provided, default to the ICA controlled by the sender
+        /// If not provided, default to the ICA controlled by the sender.
+        /// May contain placeholders.
         recipient: Option<String>,
     },
 
@@ -52,13 +103,141 @@ pub enum Action {
     ///
     /// The message is to be in raw binary format. The ICA contract is
     /// responsible for implementing logics to interpret and handle this message.
-    Execute(Binary),
+    /// It may contain placeholders, whose values are escaped for JSON strings.
+    ///
+    /// Also decoded from the form predating `funds`, i.e. the message alone,
+    /// which is still sent by older controllers.
+    #[serde(deserialize_with = "deserialize_execute")]
+    Execute {
+        msg: Binary,
+
+        /// Coins to be sent to the ICA along with the call, denominated in
+        /// this chain's denoms (the `denom` in `ActionResult::Transfer`).
+        ///
+        /// They are drawn from the coins that earlier `Transfer` actions in the
+        /// same packet delivered to the ICA, which must have delivered at least
+        /// this much of each denom. Only native coins can be sent this way.
+        #[serde(default)]
+        funds: Vec<Coin>,
+    },
 
     /// Call the ICA contract's query entry point.
     ///
     /// The message is to be in raw binary format. The ICA contract is
     /// responsible for implementing logics to interpret and handle this message.
     Query(Binary),
+
+    /// Perform a query on the receiving chain: a smart or raw query of any
+    /// contract, or a bank, staking, IBC or stargate query.
+    ///
+    /// Unlike `Query`, this does not require the sender to own an ICA, so it
+    /// can be used for interchain queries alone. Stargate queries must be
+    /// whitelisted by the receiving chain.
+    ///
+    /// The response is returned in raw binary format, same as for `Query`.
+    QueryChain(QueryRequest<Empty>),
+
+    /// Send the actions onward to a chain that is connected to the receiving
+    /// chain, but not necessarily to the sender chain.
+    ///
+    /// Tokens in `Transfer` actions nested here are sent along with the packet,
+    /// so their denoms are those on the sender chain, same as for top-level
+    /// transfers. The intermediate chain receives them, then sends them on.
+    ///
+    /// On the destination chain, the actions are executed on behalf of
+    /// `{channel_id}/{controller}`, where `channel_id` is the channel on the
+    /// intermediate chain through which the original packet arrived.
+    ///
+    /// The sender's callback is deferred until the outcome of the forwarded
+    /// packet is known. If it fails or times out, the forwarded tokens are
+    /// refunded hop by hop back to the sender.
+    Forward {
+        connection_id: String,
+        actions:       Vec<Action>,
+    },
+
+    /// Check a condition on the receiving chain, failing the packet if it
+    /// doesn't hold.
+    ///
+    /// As actions are executed atomically, this can be used to guard the other
+    /// actions in the packet, e.g. to enforce a minimum output of a swap.
+    Assert(Assertion),
+}
+
+/// The contents of an `Execute` action, either in full, or only the message as
+/// was the case before `funds` was added
+#[derive(Deserialize)]
+#[serde(crate = "cosmwasm_schema::serde", untagged)]
+enum ExecuteRepr {
+    Legacy(Binary),
+    Current(ExecuteFields),
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "cosmwasm_schema::serde", deny_unknown_fields)]
+struct ExecuteFields {
+    msg:   Binary,
+    #[serde(default)]
+    funds: Vec<Coin>,
+}
+
+fn deserialize_execute<'de, D>(deserializer: D) -> Result<(Binary, Vec<Coin>), D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match ExecuteRepr::deserialize(deserializer)? {
+        ExecuteRepr::Legacy(msg) => Ok((msg, vec![])),
+        ExecuteRepr::Current(ExecuteFields {
+            msg,
+            funds,
+        }) => Ok((msg, funds)),
+    }
+}
+
+impl Action {
+    pub fn action_type(&self) -> ActionType {
+        match self {
+            Action::Transfer { .. }    => ActionType::Transfer,
+            Action::RegisterAccount(_) => ActionType::RegisterAccount,
+            Action::Execute { .. }     => ActionType::Execute,
+            Action::Query(_)           => ActionType::Query,
+            Action::QueryChain(_)      => ActionType::QueryChain,
+            Action::Forward { .. }     => ActionType::Forward,
+            Action::Assert(_)          => ActionType::Assert,
+        }
+    }
+
+    pub fn ty(&self) -> &'static str {
+        self.action_type().as_str()
+    }
+}
+
+/// The type of an action, without its content, e.g. for pausing all actions of
+/// a type
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum ActionType {
+    Transfer,
+    RegisterAccount,
+    Execute,
+    Query,
+    QueryChain,
+    Forward,
+    Assert,
+}
+
+impl ActionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionType::Transfer        => "transfer",
+            ActionType::RegisterAccount => "register_account",
+            ActionType::Execute         => "execute",
+            ActionType::Query           => "query",
+            ActionType::QueryChain      => "query_chain",
+            ActionType::Forward         => "forward",
+            ActionType::Assert          => "assert",
+        }
+    }
 }
 
 #[cw_serde]
@@ -95,6 +274,49 @@ pub enum RegisterOptions {
     },
 }
 
+#[cw_serde]
+pub enum Assertion {
+    /// The balance of a token held by an account.
+    Balance {
+        /// If not provided, default to the ICA controlled by the sender
+        address: Option<String>,
+
+        /// The token's denom on the receiving chain. Can be a native denom or
+        /// a cw20 token in the `cw20:{contract_addr}` format.
+        denom: String,
+
+        op:     Comparison,
+        amount: Uint128,
+    },
+
+    /// The value at a path in the JSON response of a query.
+    ///
+    /// The path consists of object keys and array indexes, e.g.
+    /// `["assets", "0", "amount"]`. The value at the path must be a string,
+    /// number, boolean or null.
+    ///
+    /// Values that are both unsigned integers (including those in strings, as
+    /// is common for `Uint128`) are compared as numbers. Otherwise, they can
+    /// only be compared for equality, by their text.
+    Query {
+        request: QueryRequest<Empty>,
+        path:    Vec<String>,
+        op:      Comparison,
+        value:   String,
+    },
+}
+
+/// How the actual value is compared to the expected one
+#[cw_serde]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
 // ------------------------------------ ack ------------------------------------
 
 /// ICS-999 packet acknowledgement
@@ -121,7 +343,79 @@ pub enum PacketAck {
     /// ```json
     /// {"failed":"codespace: wasm, code: 5"}
     /// ```
+    ///
+    /// Only sent by hosts predating `Error`.
     Failed(String),
+
+    /// The packet was rejected, or one of the actions failed to execute. Same
+    /// as for `Failed`, the entire queue of actions is considered failed, and
+    /// the actions after the failed one are not executed.
+    ///
+    /// If none of the actions before the failed one took effect, i.e. they were
+    /// all queries or assertions, the ack includes the index of the failed
+    /// action, and the error is kept in full if it is raised by the ICS-999
+    /// contract itself.
+    ///
+    /// Otherwise, their effects can only be reverted by failing the packet as a
+    /// whole, in which case wasmd redacts the error message. The index is then
+    /// unknown, and the error code is derived from the redacted message. To
+    /// learn which action failed and why regardless, send the packet with
+    /// `allow_failure` set, in which case each failed action gets an
+    /// `ActionResult::Failed`.
+    Error {
+        /// Index of the failed action. Not set if the packet was rejected
+        /// before any action was executed, or if the error is redacted.
+        index: Option<u64>,
+        code:  ErrorCode,
+        error: String,
+    },
+}
+
+/// Why an action or a packet failed.
+///
+/// Derived from the error if it is raised by the ICS-999 contract itself, or
+/// from the codespace and code of the redacted error message otherwise.
+#[cw_serde]
+pub enum ErrorCode {
+    /// The protocol, the connection, or one of the action types is paused
+    Paused,
+
+    /// The amount transferred exceeds the rate limit
+    RateLimited,
+
+    /// The packet doesn't include the trace of a token being transferred
+    TraceNotFound,
+
+    /// The sender doesn't own an interchain account
+    AccountNotFound,
+
+    /// The sender already owns an interchain account
+    AccountExists,
+
+    /// A placeholder couldn't be resolved
+    InvalidPlaceholder,
+
+    /// The condition of an `assert` action didn't hold
+    AssertionFailed,
+
+    /// A query failed
+    QueryFailed,
+
+    /// Not enough funds, e.g. for `Execute` funds, a denom creation fee or a
+    /// bank transfer
+    InsufficientFunds,
+
+    /// The action ran out of gas
+    OutOfGas,
+
+    /// A contract called by the action, such as the interchain account,
+    /// returned an error. As the ICS-999 contract's own errors are redacted
+    /// the same way, this may also be one of them if the packet is atomic and
+    /// earlier actions took effect.
+    ContractError,
+
+    /// Any other error
+    Other,
 }
 
 #[cw_serde]
@@ -138,6 +432,12 @@ pub enum ActionResult {
         /// The recipient address (in case the sender did not provide an address,
         /// they can get it here)
         recipient: String,
+
+        /// The tokenfactory denom creation fee paid for creating the new token.
+        /// Empty if no token was created or if denom creation is free, and in
+        /// acks from hosts predating it.
+        #[serde(default)]
+        denom_creation_fee: Vec<Coin>,
     },
 
     /// Result of a successfully executed `register_account` action.
@@ -157,6 +457,33 @@ pub enum ActionResult {
         /// The querying contract is responsible for decoding the response
         response: Binary,
     },
+
+    /// Result of a successfully executed `forward` action.
+    Forward {
+        /// The channel on the intermediate chain the packet was forwarded to
+        channel_id: String,
+
+        /// Sequence of the forwarded packet
+        sequence: u64,
+
+        /// Outcome of the forwarded packet. Empty in the ack sent by the
+        /// intermediate chain; filled in once it is relayed back.
+        outcome: Option<PacketOutcome>,
+    },
+
+    /// Result of an `assert` action whose condition held.
+    Assert {},
+
+    /// The action failed, which doesn't fail the packet as `allow_failure` is
+    /// set. Its state changes are reverted.
+    ///
+    /// If the error is raised by the ICS-999 contract itself, the message is
+    /// kept in full. Otherwise, e.g. if the ICA returned an error, wasmd
+    /// redacts the message, leaving only the codespace and code.
+    Failed {
+        code:  ErrorCode,
+        error: String,
+    },
 }
 
 // ----------------------------------- trace -----------------------------------
@@ -217,15 +544,23 @@ pub enum ControllerExecuteMsg {
 
 #[cw_serde]
 pub struct CallbackMsg {
-    pub endpoint: IbcEndpoint,
-    pub sequence: u64,
-    pub outcome:  PacketOutcome,
+    pub endpoint:      IbcEndpoint,
+    pub sequence:      u64,
+    pub outcome:       PacketOutcome,
+    /// The data provided by the user when dispatching the packet, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_data: Option<Binary>,
 }
 
 #[cw_serde]
 pub enum PacketOutcome {
     Success(Vec<ActionResult>),
     Failed(String),
+    Error {
+        index: Option<u64>,
+        code:  ErrorCode,
+        error: String,
+    },
     Timeout {},
 }
 
@@ -234,6 +569,9 @@ impl From<Option<PacketAck>> for PacketOutcome {
         match maybe_ack {
             Some(PacketAck::Success(results)) => PacketOutcome::Success(results),
             Some(PacketAck::Failed(error))    => PacketOutcome::Failed(error),
+            Some(PacketAck::Error { index, code, error }) => {
+                PacketOutcome::Error { index, code, error }
+            },
             None                              => PacketOutcome::Timeout {},
         }
     }
@@ -242,9 +580,64 @@ impl From<Option<PacketAck>> for PacketOutcome {
 impl PacketOutcome {
     pub fn ty(&self) -> &str {
         match self {
-            PacketOutcome::Success(_) => "success",
-            PacketOutcome::Failed(_)  => "failed",
-            PacketOutcome::Timeout {} => "timeout",
+            PacketOutcome::Success(_)    => "success",
+            PacketOutcome::Failed(_)     => "failed",
+            PacketOutcome::Error { .. }  => "error",
+            PacketOutcome::Timeout {}    => "timeout",
         }
     }
 }
+
+// ----------------------------------- Tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::from_slice;
+
+    use super::*;
+
+    #[test]
+    fn decoding_old_acks() {
+        // hosts on channels opened before the denom creation fee was recorded
+        // don't include it
+        let ack: PacketAck = from_slice(
+            br#"{"success":[{"transfer":{"denom":"uatom","new_token":true,"recipient":"larry"}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(ack, PacketAck::Success(vec![ActionResult::Transfer {
+            denom:              "uatom".into(),
+            new_token:          true,
+            recipient:          "larry".into(),
+            denom_creation_fee: vec![],
+        }]));
+    }
+
+    #[test]
+    fn decoding_old_execute_actions() {
+        // controllers predating execute funds send the message alone
+        let action: Action = from_slice(br#"{"execute":"e30="}"#).unwrap();
+        assert_eq!(action, Action::Execute {
+            msg:   b"{}".into(),
+            funds: vec![],
+        });
+
+        // the current form, with or without funds
+        let action: Action = from_slice(
+            br#"{"execute":{"msg":"e30=","funds":[{"denom":"uatom","amount":"100"}]}}"#,
+        )
+        .unwrap();
+        assert_eq!(action, Action::Execute {
+            msg:   b"{}".into(),
+            funds: vec![Coin::new(100, "uatom")],
+        });
+
+        let action: Action = from_slice(br#"{"execute":{"msg":"e30="}}"#).unwrap();
+        assert_eq!(action, Action::Execute {
+            msg:   b"{}".into(),
+            funds: vec![],
+        });
+
+        // nothing else is accepted
+        from_slice::<Action>(br#"{"execute":{"msg":"e30=","gas":100}}"#).unwrap_err();
+    }
+}
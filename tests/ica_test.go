@@ -84,15 +84,17 @@ func (suite *testSuite) TestExecuteWasm() {
 			},
 		},
 		{
-			Execute: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
-				Wasm: &wasmvmtypes.WasmMsg{
-					Execute: &wasmvmtypes.ExecuteMsg{
-						ContractAddr: suite.chainB.counterAddr.String(),
-						Msg:          []byte(`{"increment":{}}`),
-						Funds:        wasmvmtypes.Coins{},
+			Execute: &types.ExecuteAction{
+				Msg: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
+					Wasm: &wasmvmtypes.WasmMsg{
+						Execute: &wasmvmtypes.ExecuteMsg{
+							ContractAddr: suite.chainB.counterAddr.String(),
+							Msg:          []byte(`{"increment":{}}`),
+							Funds:        wasmvmtypes.Coins{},
+						},
 					},
-				},
-			}),
+				}),
+			},
 		},
 	})
 	require.NoError(suite.T(), err)
@@ -110,37 +112,43 @@ func (suite *testSuite) TestExecuteWasm() {
 	// test 2 - increment the number more times in a single packet
 	_, ack2, err := send(suite.chainA, suite.pathAB, []types.Action{
 		{
-			Execute: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
-				Wasm: &wasmvmtypes.WasmMsg{
-					Execute: &wasmvmtypes.ExecuteMsg{
-						ContractAddr: suite.chainB.counterAddr.String(),
-						Msg:          []byte(`{"increment":{}}`),
-						Funds:        wasmvmtypes.Coins{},
+			Execute: &types.ExecuteAction{
+				Msg: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
+					Wasm: &wasmvmtypes.WasmMsg{
+						Execute: &wasmvmtypes.ExecuteMsg{
+							ContractAddr: suite.chainB.counterAddr.String(),
+							Msg:          []byte(`{"increment":{}}`),
+							Funds:        wasmvmtypes.Coins{},
+						},
 					},
-				},
-			}),
+				}),
+			},
 		},
 		{
-			Execute: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
-				Wasm: &wasmvmtypes.WasmMsg{
-					Execute: &wasmvmtypes.ExecuteMsg{
-						ContractAddr: suite.chainB.counterAddr.String(),
-						Msg:          []byte(`{"increment":{}}`),
-						Funds:        wasmvmtypes.Coins{},
+			Execute: &types.ExecuteAction{
+				Msg: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
+					Wasm: &wasmvmtypes.WasmMsg{
+						Execute: &wasmvmtypes.ExecuteMsg{
+							ContractAddr: suite.chainB.counterAddr.String(),
+							Msg:          []byte(`{"increment":{}}`),
+							Funds:        wasmvmtypes.Coins{},
+						},
 					},
-				},
-			}),
+				}),
+			},
 		},
 		{
-			Execute: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
-				Wasm: &wasmvmtypes.WasmMsg{
-					Execute: &wasmvmtypes.ExecuteMsg{
-						ContractAddr: suite.chainB.counterAddr.String(),
-						Msg:          []byte(`{"increment":{}}`),
-						Funds:        wasmvmtypes.Coins{},
+			Execute: &types.ExecuteAction{
+				Msg: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
+					Wasm: &wasmvmtypes.WasmMsg{
+						Execute: &wasmvmtypes.ExecuteMsg{
+							ContractAddr: suite.chainB.counterAddr.String(),
+							Msg:          []byte(`{"increment":{}}`),
+							Funds:        wasmvmtypes.Coins{},
+						},
 					},
-				},
-			}),
+				}),
+			},
 		},
 	})
 	require.NoError(suite.T(), err)
@@ -181,15 +189,17 @@ func (suite *testSuite) TestQuery() {
 			}),
 		},
 		{
-			Execute: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
-				Wasm: &wasmvmtypes.WasmMsg{
-					Execute: &wasmvmtypes.ExecuteMsg{
-						ContractAddr: suite.chainB.counterAddr.String(),
-						Msg:          []byte(`{"increment":{}}`),
-						Funds:        wasmvmtypes.Coins{},
+			Execute: &types.ExecuteAction{
+				Msg: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
+					Wasm: &wasmvmtypes.WasmMsg{
+						Execute: &wasmvmtypes.ExecuteMsg{
+							ContractAddr: suite.chainB.counterAddr.String(),
+							Msg:          []byte(`{"increment":{}}`),
+							Funds:        wasmvmtypes.Coins{},
+						},
 					},
-				},
-			}),
+				}),
+			},
 		},
 		{
 			Query: mustMarshalJSON(suite.T(), &wasmvmtypes.QueryRequest{
@@ -229,15 +239,17 @@ func (suite *testSuite) TestCallback() {
 			},
 		},
 		{
-			Execute: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
-				Wasm: &wasmvmtypes.WasmMsg{
-					Execute: &wasmvmtypes.ExecuteMsg{
-						ContractAddr: suite.chainB.counterAddr.String(),
-						Msg:          []byte(`{"increment":{}}`),
-						Funds:        wasmvmtypes.Coins{},
+			Execute: &types.ExecuteAction{
+				Msg: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
+					Wasm: &wasmvmtypes.WasmMsg{
+						Execute: &wasmvmtypes.ExecuteMsg{
+							ContractAddr: suite.chainB.counterAddr.String(),
+							Msg:          []byte(`{"increment":{}}`),
+							Funds:        wasmvmtypes.Coins{},
+						},
 					},
-				},
-			}),
+				}),
+			},
 		},
 		{
 			Query: mustMarshalJSON(suite.T(), &wasmvmtypes.QueryRequest{
@@ -260,15 +272,17 @@ func (suite *testSuite) TestCallback() {
 	// do the same thing but with an intentionally failed packet
 	packet2, ack2, err := send(suite.chainA, suite.pathAB, []types.Action{
 		{
-			Execute: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
-				Wasm: &wasmvmtypes.WasmMsg{
-					Execute: &wasmvmtypes.ExecuteMsg{
-						ContractAddr: suite.chainB.counterAddr.String(),
-						Msg:          []byte(`{"increment_but_fail":{}}`),
-						Funds:        wasmvmtypes.Coins{},
+			Execute: &types.ExecuteAction{
+				Msg: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
+					Wasm: &wasmvmtypes.WasmMsg{
+						Execute: &wasmvmtypes.ExecuteMsg{
+							ContractAddr: suite.chainB.counterAddr.String(),
+							Msg:          []byte(`{"increment_but_fail":{}}`),
+							Funds:        wasmvmtypes.Coins{},
+						},
 					},
-				},
-			}),
+				}),
+			},
 		},
 	})
 	require.NoError(suite.T(), err)
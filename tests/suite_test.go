@@ -83,8 +83,12 @@ func setupChain(t *testing.T, chain *wasmibctesting.TestChain, coins ...sdk.Coin
 
 	// instantiate one-core contract
 	coreInstantiateMsg, err := json.Marshal(&types.CoreConfig{
-		DefaultAccountCodeID: accountStoreRes.CodeID,
-		DefaultTimeoutSecs:   600, // 10 mins
+		DefaultAccountCodeID:    accountStoreRes.CodeID,
+		DefaultTimeoutSecs:      600,    // 10 mins
+		MaxTimeoutSecs:          86_400, // 1 day
+		VoucherBackend:          types.VoucherBackend{TokenFactory: &types.TokenFactoryVoucherBackend{Flavor: "osmosis"}},
+		DefaultCallbackGasLimit: 1_000_000,
+		MaxCallbackGasLimit:     5_000_000,
 	})
 	require.NoError(t, err)
 	core := chain.InstantiateContract(coreStoreRes.CodeID, coreInstantiateMsg)
@@ -317,12 +321,12 @@ func denomHashFromTrace(trace types.Trace) string {
 
 func requirePacketSuccess(t *testing.T, ack *types.PacketAck) {
 	require.NotEmpty(t, ack.Success)
-	require.Empty(t, ack.Failed)
+	require.Nil(t, ack.Error)
 }
 
 func requirePacketFailed(t *testing.T, ack *types.PacketAck) {
 	require.Empty(t, ack.Success)
-	require.NotEmpty(t, ack.Failed)
+	require.NotNil(t, ack.Error)
 }
 
 func requireBalanceEqual(t *testing.T, chain *testChain, addr sdk.AccAddress, denom string, expBalance int64) {
@@ -364,7 +368,7 @@ func requireOutcomeSuccess(t *testing.T, chain *testChain, portID, channelID str
 	outcome, err := queryOutcome(chain, portID, channelID, sequence)
 	require.NoError(t, err)
 	require.NotNil(t, outcome.Success)
-	require.Empty(t, outcome.Failed)
+	require.Nil(t, outcome.Error)
 	require.Nil(t, outcome.Timeout)
 }
 
@@ -372,7 +376,7 @@ func requireOutcomeFailed(t *testing.T, chain *testChain, portID, channelID stri
 	outcome, err := queryOutcome(chain, portID, channelID, sequence)
 	require.NoError(t, err)
 	require.Nil(t, outcome.Success)
-	require.NotEmpty(t, outcome.Failed)
+	require.NotNil(t, outcome.Error)
 	require.Nil(t, outcome.Timeout)
 }
 
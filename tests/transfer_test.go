@@ -277,26 +277,30 @@ func (suite *testSuite) TestSwap() {
 			},
 		},
 		{
-			Execute: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
-				Wasm: &wasmvmtypes.WasmMsg{
-					Execute: &wasmvmtypes.ExecuteMsg{
-						ContractAddr: dexAddr.String(),
-						Msg:          swapMsg,
-						Funds:        []wasmvmtypes.Coin{wasmvmtypes.NewCoin(uint64(amountAB), astroB)},
+			Execute: &types.ExecuteAction{
+				Msg: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
+					Wasm: &wasmvmtypes.WasmMsg{
+						Execute: &wasmvmtypes.ExecuteMsg{
+							ContractAddr: dexAddr.String(),
+							Msg:          swapMsg,
+							Funds:        []wasmvmtypes.Coin{wasmvmtypes.NewCoin(uint64(amountAB), astroB)},
+						},
 					},
-				},
-			}),
+				}),
+			},
 		},
 		{
-			Execute: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
-				Wasm: &wasmvmtypes.WasmMsg{
-					Execute: &wasmvmtypes.ExecuteMsg{
-						ContractAddr: suite.chainB.coreAddr.String(),
-						Msg:          sendBackMsg,
-						Funds:        []wasmvmtypes.Coin{wasmvmtypes.NewCoin(uint64(amountAB), "uusdc")},
+			Execute: &types.ExecuteAction{
+				Msg: mustMarshalJSON(suite.T(), &wasmvmtypes.CosmosMsg{
+					Wasm: &wasmvmtypes.WasmMsg{
+						Execute: &wasmvmtypes.ExecuteMsg{
+							ContractAddr: suite.chainB.coreAddr.String(),
+							Msg:          sendBackMsg,
+							Funds:        []wasmvmtypes.Coin{wasmvmtypes.NewCoin(uint64(amountAB), "uusdc")},
+						},
 					},
-				},
-			}),
+				}),
+			},
 		},
 	})
 	require.NoError(suite.T(), err)
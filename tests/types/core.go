@@ -3,8 +3,25 @@ package types
 import wasmvmtypes "github.com/CosmWasm/wasmvm/types"
 
 type CoreConfig struct {
-	DefaultAccountCodeID uint64 `json:"default_account_code_id"`
-	DefaultTimeoutSecs   uint64 `json:"default_timeout_secs"`
+	DefaultAccountCodeID    uint64         `json:"default_account_code_id"`
+	DefaultTimeoutSecs      uint64         `json:"default_timeout_secs"`
+	MaxTimeoutSecs          uint64         `json:"max_timeout_secs"`
+	VoucherBackend          VoucherBackend `json:"voucher_backend"`
+	DefaultCallbackGasLimit uint64         `json:"default_callback_gas_limit"`
+	MaxCallbackGasLimit     uint64         `json:"max_callback_gas_limit"`
+}
+
+type VoucherBackend struct {
+	TokenFactory *TokenFactoryVoucherBackend `json:"token_factory,omitempty"`
+	Cw20         *Cw20VoucherBackend         `json:"cw20,omitempty"`
+}
+
+type TokenFactoryVoucherBackend struct {
+	Flavor string `json:"flavor"`
+}
+
+type Cw20VoucherBackend struct {
+	CodeID uint64 `json:"code_id"`
 }
 
 type CoreExecuteMsg struct {
@@ -13,17 +30,27 @@ type CoreExecuteMsg struct {
 }
 
 type Dispatch struct {
-	ConnectionID string                  `json:"connection_id"`
-	Actions      []Action                `json:"actions"`
-	Timeout      *wasmvmtypes.IBCTimeout `json:"timeout,omitempty"`
+	ConnectionID string           `json:"connection_id"`
+	Actions      []Action         `json:"actions"`
+	Timeout      *DispatchTimeout `json:"timeout,omitempty"`
+}
+
+// DispatchTimeout is either absolute, with a block and/or a timestamp, or
+// relative, with a number of seconds
+type DispatchTimeout struct {
+	Block     *wasmvmtypes.IBCTimeoutBlock `json:"block,omitempty"`
+	Timestamp uint64                       `json:"timestamp,string,omitempty"`
+	Secs      uint64                       `json:"secs,omitempty"`
 }
 
 type Handle struct {
 	CounterpartyEndpoint wasmvmtypes.IBCEndpoint `json:"counterparty_endpoint"`
 	Endpoint             wasmvmtypes.IBCEndpoint `json:"endpoint"`
+	Sequence             uint64                  `json:"sequence"`
 	Controller           string                  `json:"controller"`
 	Actions              []Action                `json:"actions"`
 	Traces               []Trace                 `json:"traces"`
+	AllowFailure         bool                    `json:"allow_failure"`
 }
 
 type CoreQueryMsg struct {
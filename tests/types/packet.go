@@ -12,7 +12,7 @@ import (
 
 const (
 	Order   = channeltypes.UNORDERED
-	Version = "ics999-1"
+	Version = "ics999-2"
 )
 
 // ---------------------------------- packet -----------------------------------
@@ -26,7 +26,7 @@ type PacketData struct {
 type Action struct {
 	Transfer        *TransferAction        `json:"transfer,omitempty"`
 	RegisterAccount *RegisterAccountAction `json:"register_account,omitempty"`
-	Execute         []byte                 `json:"execute,omitempty"`
+	Execute         *ExecuteAction         `json:"execute,omitempty"`
 	Query           []byte                 `json:"query,omitempty"`
 }
 
@@ -36,6 +36,11 @@ type TransferAction struct {
 	Recipient string  `json:"recipient,omitempty"`
 }
 
+type ExecuteAction struct {
+	Msg   []byte             `json:"msg"`
+	Funds []wasmvmtypes.Coin `json:"funds,omitempty"`
+}
+
 type RegisterAccountAction struct {
 	Default       *RegisterAccountDefault       `json:"default,omitempty"`
 	CustomFactory *RegisterAccountCustomFactory `json:"custom_factory,omitempty"`
@@ -55,6 +60,13 @@ type RegisterAccountCustomFactory struct {
 type PacketAck struct {
 	Success []ActionResult `json:"success,omitempty"`
 	Failed  string         `json:"failed,omitempty"`
+	Error   *PacketError   `json:"error,omitempty"`
+}
+
+type PacketError struct {
+	Index *uint64 `json:"index"`
+	Code  string  `json:"code"`
+	Error string  `json:"error"`
 }
 
 type ActionResult struct {
@@ -65,9 +77,10 @@ type ActionResult struct {
 }
 
 type TransferResult struct {
-	Denom     string `json:"denom"`
-	NewToken  bool   `json:"new_token"`
-	Recipient string `json:"recipient"`
+	Denom            string             `json:"denom"`
+	NewToken         bool               `json:"new_token"`
+	Recipient        string             `json:"recipient"`
+	DenomCreationFee []wasmvmtypes.Coin `json:"denom_creation_fee"`
 }
 
 type RegisterAccountResult struct {
@@ -126,6 +139,7 @@ type CallbackMsg struct {
 type PacketOutcome struct {
 	Success []ActionResult `json:"success,omitempty"`
 	Failed  string         `json:"failed,omitempty"`
+	Error   *PacketError   `json:"error,omitempty"`
 	Timeout *Timeout       `json:"timeout,omitempty"`
 }
 